@@ -3,7 +3,13 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use test_empty_master::msg::{CountResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use test_empty_master::msg::{
+    AggregateSlaveCountsResponse, BroadcastToSlavesResponse, CollectedFeesResponse, ConfigResponse,
+    CountAtHeightResponse, CountHistoryResponse, CountResponse, DeploySlavesResponse, ExecuteMsg,
+    FailedDeploymentsResponse, InstantiateMsg, MigrateSlavesResponse, NamedCounterResponse,
+    NamedCountersResponse, PredictSlaveAddressResponse, QueryMsg, QuotaResponse, SlaveResponse,
+    SlavesResponse, TemplatesResponse, UserCountResponse, UserCountsResponse,
+};
 use test_empty_master::state::State;
 
 fn main() {
@@ -17,4 +23,22 @@ fn main() {
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(State), &out_dir);
     export_schema(&schema_for!(CountResponse), &out_dir);
+    export_schema(&schema_for!(CountAtHeightResponse), &out_dir);
+    export_schema(&schema_for!(CountHistoryResponse), &out_dir);
+    export_schema(&schema_for!(UserCountResponse), &out_dir);
+    export_schema(&schema_for!(UserCountsResponse), &out_dir);
+    export_schema(&schema_for!(NamedCounterResponse), &out_dir);
+    export_schema(&schema_for!(NamedCountersResponse), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(CollectedFeesResponse), &out_dir);
+    export_schema(&schema_for!(PredictSlaveAddressResponse), &out_dir);
+    export_schema(&schema_for!(DeploySlavesResponse), &out_dir);
+    export_schema(&schema_for!(MigrateSlavesResponse), &out_dir);
+    export_schema(&schema_for!(BroadcastToSlavesResponse), &out_dir);
+    export_schema(&schema_for!(SlaveResponse), &out_dir);
+    export_schema(&schema_for!(SlavesResponse), &out_dir);
+    export_schema(&schema_for!(TemplatesResponse), &out_dir);
+    export_schema(&schema_for!(AggregateSlaveCountsResponse), &out_dir);
+    export_schema(&schema_for!(QuotaResponse), &out_dir);
+    export_schema(&schema_for!(FailedDeploymentsResponse), &out_dir);
 }
@@ -1,22 +1,56 @@
+use std::convert::TryFrom;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, WasmMsg, SubMsg, CosmosMsg, StdError, SubMsgResult, Reply};
+use cosmwasm_std::{
+    from_json, instantiate2_address, to_json_binary, Addr, BankMsg, Binary, Coin, Coins, CosmosMsg,
+    Deps, DepsMut, Env, Event, HexBinary, MessageInfo, Order, OverflowError, OverflowOperation,
+    Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResult, WasmMsg,
+};
 use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use cw_utils::{parse_execute_response_data, parse_instantiate_response_data};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
-use crate::msg::{CountResponse, ExecuteMsg, InstantiateMsg, QueryMsg, SlaveInstantiateMsg};
-use crate::state::{State, STATE};
+use crate::msg::{
+    AdminPolicy, AggregateSlaveCountsResponse, BroadcastToSlavesResponse, CollectedFee,
+    CollectedFeesResponse, ConfigResponse, CountAtHeightResponse, CountChangeResponse,
+    CountHistoryResponse, CountResponse, DecommissionMode, DeploySlavesResponse, ExecuteMsg,
+    FailedDeploymentResponse, FailedDeploymentsResponse, ForwardFunds, InstantiateMsg,
+    MigrateSlavesResponse, NamedCounterResponse, NamedCountersResponse,
+    PredictSlaveAddressResponse, QueryMsg, QuotaResponse, SlaveCount, SlaveExecuteMsg,
+    SlaveInstantiateMsg, SlaveResponse, SlaveSalt, SlaveSpec, SlavesResponse, TemplateResponse,
+    TemplatesResponse, UserCountResponse, UserCountsResponse,
+};
+use crate::state::{
+    BatchPosition, Config, CountBounds, CountChange, FailedDeployment, FailurePolicy, FeeTotal,
+    MigrationRecord, NamedCounter, PendingDeployment, PendingMigration, Quotas, SlaveInfo,
+    SlaveStatus, SlaveTemplate, State, BATCH_SLAVES, CONFIG, COUNT_HISTORY, CREATOR_STATS,
+    EARLY_REGISTRATIONS, FAILED_DEPLOYMENTS, FEES, LIVE_SLAVES, NAMED_COUNTERS,
+    PENDING_DEPLOYMENTS, PENDING_MIGRATIONS, PENDING_RELAYS, REPLY_ID, SALT_NONCES, SLAVES,
+    SLAVE_SEQ, STATE, TEMPLATES, USER_COUNTS,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:test-empty-master";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-const INSTANTIATE_REPLY_ID: u64 = 1;
+const DEFAULT_SLAVE_LABEL: &str = "DeployedSlave";
+const MAX_LABEL_LENGTH: usize = 128;
+// leaves room for the "-<seq>" suffix of default labels
+const MAX_LABEL_PREFIX_LENGTH: usize = MAX_LABEL_LENGTH - 21;
+const MAX_COUNTER_NAME_LENGTH: usize = 64;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -24,42 +58,652 @@ pub fn instantiate(
         count: msg.count,
         owner: info.sender.clone(),
     };
+    validate_count_bounds(&msg.count_bounds, msg.count)?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     STATE.save(deps.storage, &state)?;
+    record_count_change(deps.storage, &env, &info.sender, 0, state.count)?;
+    add_user_count(deps.storage, &info.sender, i64::from(state.count))?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            slave_code_id: msg.slave_code_id,
+            slave_checksum: msg.slave_checksum,
+            allowed_denoms: msg.allowed_denoms,
+            deploy_fee: Coins::try_from(msg.deploy_fee)?.into_vec(),
+            max_batch_size: msg.max_batch_size,
+            quotas: msg.quotas,
+            failure_policy: msg.failure_policy,
+            count_bounds: msg.count_bounds,
+        },
+    )?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("owner", info.sender)
-        .add_attribute("count", msg.count.to_string()))
+        .add_attribute("count", msg.count.to_string())
+        .add_attribute("slave_code_id", msg.slave_code_id.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Increment {} => try_increment(deps),
-        ExecuteMsg::DeploySlave {count} => deploy_slave(deps, _env, info, count),
+        ExecuteMsg::Increment {} => try_increment(deps, env, info),
+        ExecuteMsg::Decrement {} => try_decrement(deps, env, info),
+        ExecuteMsg::Add { amount } => try_add(deps, env, info, amount),
+        ExecuteMsg::Set { value } | ExecuteMsg::Reset { count: value } => {
+            try_set(deps, env, info, value)
+        }
+        ExecuteMsg::CreateCounter {
+            name,
+            initial,
+            owner,
+            bounds,
+        } => try_create_counter(deps, info, name, initial, owner, bounds),
+        ExecuteMsg::IncrementNamed { name } => try_increment_named(deps, name),
+        ExecuteMsg::ResetNamed { name, count } => try_reset_named(deps, info, name, count),
+        ExecuteMsg::UpdateNamedCounterBounds { name, bounds } => {
+            try_update_named_counter_bounds(deps, info, name, bounds)
+        }
+        ExecuteMsg::DeploySlave {
+            template,
+            count,
+            salt,
+            forward_funds,
+            label,
+            admin,
+        } => deploy_slave(
+            deps,
+            env,
+            info,
+            SlaveSpec {
+                template,
+                count,
+                salt,
+                forward_funds,
+                label,
+                admin,
+            },
+        ),
+        ExecuteMsg::DeploySlaves { slaves } => deploy_slaves(deps, env, info, slaves),
+        ExecuteMsg::ExecuteOnSlave {
+            slave,
+            msg,
+            funds,
+            return_data,
+        } => execute_on_slave(deps, info, slave, msg, funds, return_data),
+        ExecuteMsg::TransferSlave {
+            slave,
+            new_owner,
+            require_accept,
+        } => transfer_slave(deps, info, slave, new_owner, require_accept),
+        ExecuteMsg::AcceptSlave { slave } => accept_slave(deps, info, slave),
+        ExecuteMsg::CancelSlaveTransfer { slave } => cancel_slave_transfer(deps, info, slave),
+        ExecuteMsg::RegisterSlave {} => register_slave(deps, env, info),
+        ExecuteMsg::DecommissionSlave { slave, mode } => {
+            decommission_slave(deps, env, info, slave, mode)
+        }
+        ExecuteMsg::BroadcastToSlaves {
+            action,
+            start_after,
+            limit,
+        } => broadcast_to_slaves(deps, info, action, start_after, limit),
+        ExecuteMsg::MigrateSlaves {
+            new_code_id,
+            checksum,
+            msg,
+            start_after,
+            limit,
+        } => migrate_slaves(
+            deps,
+            env,
+            info,
+            new_code_id,
+            checksum,
+            msg,
+            start_after,
+            limit,
+        ),
+        ExecuteMsg::UpdateSlaveCodeId { code_id, checksum } => {
+            try_update_slave_code_id(deps, info, code_id, checksum)
+        }
+        ExecuteMsg::UpdateMaxBatchSize { max_batch_size } => {
+            try_update_max_batch_size(deps, info, max_batch_size)
+        }
+        ExecuteMsg::UpdateAllowedDenoms { denoms } => try_update_allowed_denoms(deps, info, denoms),
+        ExecuteMsg::UpdateDeployFee { fee } => try_update_deploy_fee(deps, info, fee),
+        ExecuteMsg::UpdateQuotas { quotas } => try_update_quotas(deps, info, quotas),
+        ExecuteMsg::UpdateFailurePolicy { policy } => try_update_failure_policy(deps, info, policy),
+        ExecuteMsg::UpdateCountBounds { bounds } => try_update_count_bounds(deps, info, bounds),
+        ExecuteMsg::WithdrawFees { to, amount } => try_withdraw_fees(deps, info, to, amount),
+        ExecuteMsg::SetTemplate {
+            name,
+            code_id,
+            checksum,
+            label_prefix,
+            default_msg,
+            enabled,
+        } => try_set_template(
+            deps,
+            env,
+            info,
+            name,
+            SlaveTemplate {
+                code_id,
+                checksum,
+                label_prefix,
+                default_msg,
+                enabled,
+            },
+        ),
+        ExecuteMsg::RemoveTemplate { name } => try_remove_template(deps, info, name),
+    }
+}
+
+fn ensure_owner(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if *sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+pub fn try_increment(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    update_count(deps, env, info, "try_increment", |count| {
+        count.checked_add(1)
+    })
+}
+
+pub fn try_decrement(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    update_count(deps, env, info, "try_decrement", |count| {
+        count.checked_sub(1)
+    })
+}
+
+pub fn try_add(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: i32,
+) -> Result<Response, ContractError> {
+    update_count(deps, env, info, "try_add", |count| {
+        count.checked_add(amount)
+    })
+}
+
+pub fn try_set(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    value: i32,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    update_count(deps, env, info, "try_set", |_| Some(value))
+}
+
+/// Applies `op` to the counter, records the change in the history and
+/// reports the old and new value in a `counter` event. `op` returns None on
+/// overflow.
+fn update_count(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    method: &str,
+    op: impl FnOnce(i32) -> Option<i32>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    let old = state.count;
+    state.count = op(old).ok_or(ContractError::CounterOverflow {})?;
+    let bounds = CONFIG.load(deps.storage)?.count_bounds;
+    if !bounds.contains(state.count) {
+        return Err(ContractError::CounterOutOfBounds { count: state.count });
+    }
+    STATE.save(deps.storage, &state)?;
+    record_count_change(deps.storage, &env, &info.sender, old, state.count)?;
+    // the change is credited to the sender so the user counts keep adding up
+    add_user_count(
+        deps.storage,
+        &info.sender,
+        i64::from(state.count) - i64::from(old),
+    )?;
+
+    Ok(Response::new().add_attribute("method", method).add_event(
+        Event::new("counter")
+            .add_attribute("old", old.to_string())
+            .add_attribute("new", state.count.to_string()),
+    ))
+}
+
+fn record_count_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+    actor: &Addr,
+    old: i32,
+    new: i32,
+) -> StdResult<()> {
+    let id = checked_increment(last_count_change_id(storage)?.unwrap_or_default())?;
+    COUNT_HISTORY.save(
+        storage,
+        id,
+        &CountChange {
+            height: env.block.height,
+            time: env.block.time,
+            actor: actor.clone(),
+            old,
+            new,
+        },
+    )
+}
+
+fn last_count_change_id(storage: &dyn Storage) -> StdResult<Option<u64>> {
+    COUNT_HISTORY
+        .keys(storage, None, None, Order::Descending)
+        .next()
+        .transpose()
+}
+
+fn add_user_count(storage: &mut dyn Storage, user: &Addr, delta: i64) -> Result<(), ContractError> {
+    let count = USER_COUNTS.may_load(storage, user)?.unwrap_or_default();
+    let count = count
+        .checked_add(delta)
+        .ok_or(ContractError::CounterOverflow {})?;
+    USER_COUNTS.save(storage, user, &count)?;
+    Ok(())
+}
+
+pub fn try_create_counter(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    initial: i32,
+    owner: Option<String>,
+    bounds: CountBounds,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    validate_counter_name(&name)?;
+    if NAMED_COUNTERS.has(deps.storage, &name) {
+        return Err(ContractError::CounterExists { name });
+    }
+    validate_count_bounds(&bounds, initial)?;
+    let owner = match owner {
+        Some(owner) => deps.api.addr_validate(&owner)?,
+        None => info.sender,
+    };
+    NAMED_COUNTERS.save(
+        deps.storage,
+        &name,
+        &NamedCounter {
+            owner: owner.clone(),
+            count: initial,
+            bounds,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_create_counter")
+        .add_attribute("name", name)
+        .add_attribute("owner", owner)
+        .add_attribute("count", initial.to_string()))
+}
+
+pub fn try_increment_named(deps: DepsMut, name: String) -> Result<Response, ContractError> {
+    let counter = load_counter(deps.as_ref(), &name)?;
+    update_named_count(deps, name, counter, "try_increment_named", |count| {
+        count.checked_add(1)
+    })
+}
+
+pub fn try_reset_named(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    count: i32,
+) -> Result<Response, ContractError> {
+    let counter = load_counter(deps.as_ref(), &name)?;
+    if info.sender != counter.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    update_named_count(deps, name, counter, "try_reset_named", |_| Some(count))
+}
+
+pub fn try_update_named_counter_bounds(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    bounds: CountBounds,
+) -> Result<Response, ContractError> {
+    let mut counter = load_counter(deps.as_ref(), &name)?;
+    if info.sender != counter.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    validate_count_bounds(&bounds, counter.count)?;
+    counter.bounds = bounds;
+    NAMED_COUNTERS.save(deps.storage, &name, &counter)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_update_named_counter_bounds")
+        .add_attribute("name", name))
+}
+
+fn load_counter(deps: Deps, name: &str) -> Result<NamedCounter, ContractError> {
+    NAMED_COUNTERS
+        .may_load(deps.storage, name)?
+        .ok_or_else(|| ContractError::UnknownCounter {
+            name: name.to_string(),
+        })
+}
+
+/// Like `update_count` for a named counter, which keeps no history or user
+/// counts.
+fn update_named_count(
+    deps: DepsMut,
+    name: String,
+    mut counter: NamedCounter,
+    method: &str,
+    op: impl FnOnce(i32) -> Option<i32>,
+) -> Result<Response, ContractError> {
+    let old = counter.count;
+    counter.count = op(old).ok_or(ContractError::CounterOverflow {})?;
+    if !counter.bounds.contains(counter.count) {
+        return Err(ContractError::CounterOutOfBounds {
+            count: counter.count,
+        });
+    }
+    NAMED_COUNTERS.save(deps.storage, &name, &counter)?;
+
+    Ok(Response::new().add_attribute("method", method).add_event(
+        Event::new("counter")
+            .add_attribute("name", name)
+            .add_attribute("old", old.to_string())
+            .add_attribute("new", counter.count.to_string()),
+    ))
+}
+
+fn validate_counter_name(name: &str) -> Result<(), ContractError> {
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    if name.is_empty() || name.len() > MAX_COUNTER_NAME_LENGTH || !name.chars().all(valid_char) {
+        return Err(ContractError::InvalidCounterName {
+            max: MAX_COUNTER_NAME_LENGTH,
+        });
+    }
+    Ok(())
+}
+
+pub fn try_update_slave_code_id(
+    deps: DepsMut,
+    info: MessageInfo,
+    code_id: u64,
+    checksum: HexBinary,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+        config.slave_code_id = code_id;
+        config.slave_checksum = checksum.clone();
+        Ok(config)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_update_slave_code_id")
+        .add_attribute("slave_code_id", code_id.to_string())
+        .add_attribute("checksum", checksum.to_hex()))
+}
+
+pub fn try_update_max_batch_size(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_batch_size: u32,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+        config.max_batch_size = max_batch_size;
+        Ok(config)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_update_max_batch_size")
+        .add_attribute("max_batch_size", max_batch_size.to_string()))
+}
+
+pub fn try_update_quotas(
+    deps: DepsMut,
+    info: MessageInfo,
+    quotas: Quotas,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+        config.quotas = quotas;
+        Ok(config)
+    })?;
+
+    Ok(Response::new().add_attribute("method", "try_update_quotas"))
+}
+
+pub fn try_update_failure_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    policy: FailurePolicy,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+        config.failure_policy = policy;
+        Ok(config)
+    })?;
+
+    Ok(Response::new().add_attribute("method", "try_update_failure_policy"))
+}
+
+pub fn try_update_count_bounds(
+    deps: DepsMut,
+    info: MessageInfo,
+    bounds: CountBounds,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    let count = STATE.load(deps.storage)?.count;
+    validate_count_bounds(&bounds, count)?;
+    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+        config.count_bounds = bounds;
+        Ok(config)
+    })?;
+
+    Ok(Response::new().add_attribute("method", "try_update_count_bounds"))
+}
+
+/// Bounds must form a range that includes the current count.
+fn validate_count_bounds(bounds: &CountBounds, count: i32) -> Result<(), ContractError> {
+    if let (Some(min), Some(max)) = (bounds.min, bounds.max) {
+        if min > max {
+            return Err(ContractError::InvalidCountBounds {});
+        }
     }
+    if !bounds.contains(count) {
+        return Err(ContractError::CounterOutOfBounds { count });
+    }
+    Ok(())
+}
+
+pub fn try_update_allowed_denoms(
+    deps: DepsMut,
+    info: MessageInfo,
+    denoms: Vec<String>,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+        config.allowed_denoms = denoms.clone();
+        Ok(config)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_update_allowed_denoms")
+        .add_attribute("allowed_denoms", denoms.join(",")))
 }
 
-pub fn try_increment(deps: DepsMut) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        state.count += 1;
-        Ok(state)
+pub fn try_update_deploy_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    fee: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    let fee = Coins::try_from(fee)?;
+    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+        config.deploy_fee = fee.to_vec();
+        Ok(config)
     })?;
 
-    Ok(Response::new().add_attribute("method", "try_increment"))
+    Ok(Response::new()
+        .add_attribute("method", "try_update_deploy_fee")
+        .add_attribute("deploy_fee", fee.to_string()))
+}
+
+pub fn try_withdraw_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    to: String,
+    amount: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    let to = deps.api.addr_validate(&to)?;
+    // zero amounts are dropped by the conversion
+    let amount = Coins::try_from(amount)?;
+    if amount.is_empty() {
+        return Err(ContractError::EmptyWithdrawal {});
+    }
+
+    for coin in amount.iter() {
+        let mut total = FEES
+            .may_load(deps.storage, &coin.denom)?
+            .unwrap_or_default();
+        if total.collected.checked_sub(total.withdrawn)? < coin.amount {
+            return Err(ContractError::InsufficientTreasury {
+                denom: coin.denom.clone(),
+            });
+        }
+        total.withdrawn = total.withdrawn.checked_add(coin.amount)?;
+        FEES.save(deps.storage, &coin.denom, &total)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "try_withdraw_fees")
+        .add_attribute("to", to.as_str())
+        .add_attribute("amount", amount.to_string())
+        .add_message(BankMsg::Send {
+            to_address: to.into_string(),
+            amount: amount.into_vec(),
+        }))
+}
+
+/// The one field of a template default message the master relies on
+#[derive(Deserialize)]
+struct DefaultMsgMaster {
+    master: String,
+}
+
+pub fn try_set_template(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    template: SlaveTemplate,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    if name.is_empty() {
+        return Err(ContractError::InvalidTemplateName {});
+    }
+    if !is_valid_label(&template.label_prefix, MAX_LABEL_PREFIX_LENGTH) {
+        return Err(ContractError::InvalidLabelPrefix {
+            max: MAX_LABEL_PREFIX_LENGTH,
+        });
+    }
+    // slaves deployed from the default message have to find their way back
+    // to this master to register
+    if let Some(default_msg) = &template.default_msg {
+        match from_json::<DefaultMsgMaster>(default_msg) {
+            Ok(DefaultMsgMaster { master }) if master == env.contract.address.as_str() => {}
+            _ => return Err(ContractError::InvalidDefaultMsg {}),
+        }
+    }
+    TEMPLATES.save(deps.storage, &name, &template)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_template")
+        .add_attribute("template", name)
+        .add_attribute("code_id", template.code_id.to_string())
+        .add_attribute("enabled", template.enabled.to_string()))
 }
 
+pub fn try_remove_template(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    if !TEMPLATES.has(deps.storage, &name) {
+        return Err(ContractError::UnknownTemplate { name });
+    }
+    TEMPLATES.remove(deps.storage, &name);
+
+    Ok(Response::new()
+        .add_attribute("method", "try_remove_template")
+        .add_attribute("template", name))
+}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetCount {} => to_binary(&query_count(deps)?),
+        QueryMsg::GetCount {} => to_json_binary(&query_count(deps)?),
+        QueryMsg::GetUserCount { address } => to_json_binary(&query_user_count(deps, address)?),
+        QueryMsg::UserCounts { start_after, limit } => {
+            to_json_binary(&query_user_counts(deps, start_after, limit)?)
+        }
+        QueryMsg::NamedCounter { name } => to_json_binary(&query_named_counter(deps, name)?),
+        QueryMsg::NamedCounters { start_after, limit } => {
+            to_json_binary(&query_named_counters(deps, start_after, limit)?)
+        }
+        QueryMsg::GetCountAtHeight { height } => {
+            to_json_binary(&query_count_at_height(deps, height)?)
+        }
+        QueryMsg::CountHistory { start_after, limit } => {
+            to_json_binary(&query_count_history(deps, start_after, limit)?)
+        }
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::CollectedFees {} => to_json_binary(&query_collected_fees(deps)?),
+        QueryMsg::Templates { start_after, limit } => {
+            to_json_binary(&query_templates(deps, start_after, limit)?)
+        }
+        QueryMsg::PredictSlaveAddress {
+            template,
+            salt,
+            creator,
+        } => to_json_binary(&query_predict_slave_address(
+            deps, env, template, salt, creator,
+        )?),
+        QueryMsg::Slave { address } => to_json_binary(&query_slave(deps, address)?),
+        QueryMsg::ListSlaves { start_after, limit } => {
+            to_json_binary(&query_list_slaves(deps, start_after, limit)?)
+        }
+        QueryMsg::FailedDeployments { start_after, limit } => {
+            to_json_binary(&query_failed_deployments(deps, start_after, limit)?)
+        }
+        QueryMsg::Quota { address } => to_json_binary(&query_quota(deps, env, address)?),
+        QueryMsg::SlavesByOwner {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&query_slaves_by_owner(deps, owner, start_after, limit)?),
+        QueryMsg::AggregateSlaveCounts { start_after, limit } => {
+            to_json_binary(&query_aggregate_slave_counts(deps, start_after, limit)?)
+        }
     }
 }
 
@@ -68,126 +712,2831 @@ fn query_count(deps: Deps) -> StdResult<CountResponse> {
     Ok(CountResponse { count: state.count })
 }
 
-pub fn deploy_slave(mut deps: DepsMut, env: Env, info: MessageInfo, count: i32) -> Result<Response, ContractError> {
-    let instantiate_message: WasmMsg = WasmMsg::Instantiate {
-        admin: Some(env.contract.address.to_string()),
-        code_id: 9552,
-        msg: to_binary(&SlaveInstantiateMsg {
-            count: count
-        })?,
-        funds: vec![],
-        label: "DeployedSlave".to_string(),
-    };
+fn query_user_count(deps: Deps, address: String) -> StdResult<UserCountResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let count = USER_COUNTS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(UserCountResponse { address, count })
+}
 
-    let sub_msg: SubMsg = SubMsg::reply_always(CosmosMsg::Wasm(instantiate_message.into()), INSTANTIATE_REPLY_ID);
+fn query_user_counts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<UserCountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
 
-    Ok(Response::new()
-        .add_attribute("method", "DeployedSlave")
-        .add_submessage(sub_msg))
-}
+    let counts = USER_COUNTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(address, count)| UserCountResponse { address, count }))
+        .collect::<StdResult<_>>()?;
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> StdResult<Response> {
-    match msg.id {
-        INSTANTIATE_REPLY_ID => handle_instantiate_reply(deps, msg),
+    Ok(UserCountsResponse { counts })
+}
 
-        id => Err(StdError::generic_err(format!("Unknown reply id: {}", id))),
-    }
+fn query_named_counter(deps: Deps, name: String) -> StdResult<NamedCounterResponse> {
+    let counter = NAMED_COUNTERS.load(deps.storage, &name)?;
+    Ok(NamedCounterResponse {
+        name,
+        owner: counter.owner,
+        count: counter.count,
+        bounds: counter.bounds,
+    })
 }
 
-pub fn handle_instantiate_reply(deps: DepsMut, msg: Reply) -> StdResult<Response> {
-    deps.api.debug(&format!("Status 1"));
+fn query_named_counters(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<NamedCountersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
 
-    // Ensure the result is parsed correctly
-    let result = match msg.result {
-        SubMsgResult::Ok(result) => result,
-        SubMsgResult::Err(err) => {
-            deps.api.debug(&format!("SubMsg error: {}", err));
-            return Err(StdError::generic_err(format!("SubMsg error: {}", err)));
-        }
-    };
+    let counters = NAMED_COUNTERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(name, counter)| NamedCounterResponse {
+                name,
+                owner: counter.owner,
+                count: counter.count,
+                bounds: counter.bounds,
+            })
+        })
+        .collect::<StdResult<_>>()?;
 
-    deps.api.debug(&format!("Status 2"));
+    Ok(NamedCountersResponse { counters })
+}
 
-    // Log all events for debugging purposes
-    deps.api.debug("Handling instantiate reply");
-    for event in &result.events {
-        deps.api.debug(&format!("Event: {}", event.ty));
-        for attr in &event.attributes {
-            deps.api.debug(&format!("{}: {}", attr.key, attr.value));
+fn query_count_at_height(deps: Deps, height: u64) -> StdResult<CountAtHeightResponse> {
+    // ids are handed out in block order, so bisect them for the last change
+    // up to `height`
+    let mut low = 0;
+    let mut high = last_count_change_id(deps.storage)?.unwrap_or_default();
+    while low < high {
+        let mid = high - (high - low) / 2;
+        if COUNT_HISTORY.load(deps.storage, mid)?.height <= height {
+            low = mid;
+        } else {
+            high = mid - 1;
         }
     }
+    let count = match low {
+        0 => None,
+        id => Some(COUNT_HISTORY.load(deps.storage, id)?.new),
+    };
+    Ok(CountAtHeightResponse { height, count })
+}
 
-    deps.api.debug(&format!("Status 3"));
+fn query_count_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<CountHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
 
-    // Find the event type "instantiate_contract" which contains the contract_address
-    let event = match result.events.iter().find(|event| event.ty == "instantiate") {
-        Some(event) => event,
-        None => {
-            deps.api.debug("Cannot find `instantiate` event");
-            return Err(StdError::generic_err("Cannot find `instantiate` event"));
+    let changes = COUNT_HISTORY
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(id, change)| CountChangeResponse {
+                id,
+                height: change.height,
+                time: change.time,
+                actor: change.actor,
+                old: change.old,
+                new: change.new,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(CountHistoryResponse { changes })
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let state = STATE.load(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: state.owner,
+        slave_code_id: config.slave_code_id,
+        slave_checksum: config.slave_checksum,
+        allowed_denoms: config.allowed_denoms,
+        deploy_fee: config.deploy_fee,
+        max_batch_size: config.max_batch_size,
+        quotas: config.quotas,
+        failure_policy: config.failure_policy,
+        count_bounds: config.count_bounds,
+    })
+}
+
+fn query_failed_deployments(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<FailedDeploymentsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let failures = FAILED_DEPLOYMENTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(id, failure)| FailedDeploymentResponse {
+                id,
+                requester: failure.requester,
+                code_id: failure.code_id,
+                label: failure.label,
+                error: failure.error,
+                height: failure.height,
+                refunded: failure.refunded,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(FailedDeploymentsResponse { failures })
+}
+
+fn query_quota(deps: Deps, env: Env, address: String) -> StdResult<QuotaResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let quotas = CONFIG.load(deps.storage)?.quotas;
+    let stats = CREATOR_STATS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let live = LIVE_SLAVES.may_load(deps.storage)?.unwrap_or_default();
+
+    let (remaining_in_window, window_resets_at) = match quotas.rate_limit {
+        Some(rate_limit) => {
+            let window_end = stats.window_start.saturating_add(rate_limit.window_blocks);
+            if env.block.height >= window_end {
+                (Some(rate_limit.max_deployments), None)
+            } else {
+                (
+                    Some(
+                        rate_limit
+                            .max_deployments
+                            .saturating_sub(stats.window_deployments),
+                    ),
+                    Some(window_end),
+                )
+            }
         }
+        None => (None, None),
     };
 
-    deps.api.debug(&format!("Status 4"));
+    Ok(QuotaResponse {
+        live_slaves: stats.live_slaves,
+        remaining_slaves: quotas
+            .max_slaves_per_creator
+            .map(|max| max.saturating_sub(stats.live_slaves)),
+        remaining_in_window,
+        window_resets_at,
+        remaining_global: quotas.max_slaves.map(|max| max.saturating_sub(live)),
+    })
+}
 
-    // Find the contract_address from the "instantiate" event
-    let contract_address = match event.attributes.iter().find(|attr| attr.key == "_contract_address") {
-        Some(attr) => &attr.value,
-        None => {
-            deps.api.debug("Cannot find `_contract_address` attribute");
-            return Err(StdError::generic_err("Cannot find `_contract_address` attribute"));
+fn query_collected_fees(deps: Deps) -> StdResult<CollectedFeesResponse> {
+    let fees = FEES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, total) = item?;
+            Ok(CollectedFee {
+                denom,
+                collected: total.collected,
+                withdrawn: total.withdrawn,
+                available: total.collected.checked_sub(total.withdrawn)?,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(CollectedFeesResponse { fees })
+}
+
+fn query_templates(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TemplatesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let templates = TEMPLATES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(name, template)| TemplateResponse {
+                name,
+                code_id: template.code_id,
+                checksum: template.checksum,
+                label_prefix: template.label_prefix,
+                default_msg: template.default_msg,
+                enabled: template.enabled,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(TemplatesResponse { templates })
+}
+
+fn query_predict_slave_address(
+    deps: Deps,
+    env: Env,
+    template: Option<String>,
+    salt: Option<Binary>,
+    creator: String,
+) -> StdResult<PredictSlaveAddressResponse> {
+    let creator = deps.api.addr_validate(&creator)?;
+    let salt = match salt {
+        Some(salt) => {
+            scoped_salt(&creator, &salt).map_err(|err| StdError::generic_err(err.to_string()))?
         }
+        None => derived_salt(deps, &creator)?,
     };
 
-    deps.api.debug(&format!("Status 5"));
+    let code_id = resolve_template(deps, template.as_deref())
+        .map_err(|err| StdError::generic_err(err.to_string()))?
+        .code_id;
+    let address = predict_slave_address(deps, &env, code_id, &salt)?;
 
-    // Construct the response and include relevant attributes
-    Ok(Response::new()
-        .add_attribute("method", "handle_instantiate_reply")
-        .add_attribute("contract_address", contract_address))
+    Ok(PredictSlaveAddressResponse {
+        address,
+        code_id,
+        salt,
+    })
 }
 
+fn query_slave(deps: Deps, address: String) -> StdResult<SlaveResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let info = SLAVES.load(deps.storage, &address)?;
+    Ok(to_slave_response(address, info))
+}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary};
+fn query_list_slaves(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SlavesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
 
-    #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+    let slaves = SLAVES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(address, info)| to_slave_response(address, info)))
+        .collect::<StdResult<_>>()?;
 
-        let msg = InstantiateMsg { count: 17 };
-        let info = mock_info("creator", &coins(1000, "earth"));
+    Ok(SlavesResponse { slaves })
+}
 
-        // we can just call .unwrap() to assert this was a success
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+fn query_slaves_by_owner(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SlavesResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
 
-        // it worked, let's query the state
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-        let value: CountResponse = from_binary(&res).unwrap();
-        assert_eq!(17, value.count);
+    let slaves = SLAVES
+        .idx
+        .owner
+        .prefix(owner)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(address, info)| to_slave_response(address, info)))
+        .collect::<StdResult<_>>()?;
+
+    Ok(SlavesResponse { slaves })
+}
+
+fn query_aggregate_slave_counts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AggregateSlaveCountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let slaves = SLAVES
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut total = 0i64;
+    let mut counts = Vec::with_capacity(slaves.len());
+    for address in slaves {
+        // a broken slave must not take the whole page down
+        let res: StdResult<CountResponse> = deps
+            .querier
+            .query_wasm_smart(&address, &QueryMsg::GetCount {});
+        counts.push(match res {
+            Ok(res) => {
+                total += i64::from(res.count);
+                SlaveCount {
+                    address,
+                    count: Some(res.count),
+                    error: None,
+                }
+            }
+            Err(err) => SlaveCount {
+                address,
+                count: None,
+                error: Some(err.to_string()),
+            },
+        });
     }
 
-    #[test]
-    fn increment() {
-        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+    let last = if counts.len() == limit {
+        counts.last().map(|count| count.address.clone())
+    } else {
+        None
+    };
+    Ok(AggregateSlaveCountsResponse {
+        counts,
+        total,
+        last,
+    })
+}
 
-        let msg = InstantiateMsg { count: 17 };
-        let info = mock_info("creator", &coins(2, "token"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+fn to_slave_response(address: Addr, info: SlaveInfo) -> SlaveResponse {
+    SlaveResponse {
+        address,
+        creator: info.creator,
+        owner: info.owner,
+        pending_owner: info.pending_owner,
+        initial_count: info.initial_count,
+        code_id: info.code_id,
+        template: info.template,
+        label: info.label,
+        admin: info.admin,
+        height: info.height,
+        time: info.time,
+        last_migration: info.last_migration,
+        status: info.status,
+    }
+}
 
-        // beneficiary can release it
-        let info = mock_info("anyone", &coins(2, "token"));
-        let msg = ExecuteMsg::Increment {};
-        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+pub fn deploy_slave(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spec: SlaveSpec,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut remaining = Coins::try_from(info.funds.clone())?;
 
-        // should increase counter by 1
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-        let value: CountResponse = from_binary(&res).unwrap();
-        assert_eq!(18, value.count);
+    let (sub_msg, salt) = prepare_slave(
+        deps.branch(),
+        &env,
+        &config,
+        &info.sender,
+        &mut remaining,
+        spec,
+        None,
+    )?;
+
+    let mut res = Response::new()
+        .add_attribute("method", "DeployedSlave")
+        .add_submessage(sub_msg);
+    if let Some(salt) = salt {
+        res = res.add_attribute("salt", salt.to_base64());
+    }
+    Ok(refund_remaining(res, &info.sender, remaining))
+}
+
+pub fn deploy_slaves(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    slaves: Vec<SlaveSpec>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if slaves.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    if slaves.len() > config.max_batch_size as usize {
+        return Err(ContractError::BatchTooLarge {
+            max: config.max_batch_size,
+        });
+    }
+    let mut remaining = Coins::try_from(info.funds.clone())?;
+
+    let size = slaves.len() as u32;
+    let batch_id = next_reply_id(deps.storage)?;
+    let mut res = Response::new()
+        .add_attribute("method", "deploy_slaves")
+        .add_attribute("count", size.to_string());
+    for (index, spec) in slaves.into_iter().enumerate() {
+        let (sub_msg, _) = prepare_slave(
+            deps.branch(),
+            &env,
+            &config,
+            &info.sender,
+            &mut remaining,
+            spec,
+            Some(BatchPosition {
+                id: batch_id,
+                index: index as u32,
+                size,
+            }),
+        )?;
+        res = res.add_submessage(sub_msg);
+    }
+    Ok(refund_remaining(res, &info.sender, remaining))
+}
+
+/// Counts one more deployment of `creator`, failing once a quota is used up.
+fn consume_quota(
+    storage: &mut dyn Storage,
+    env: &Env,
+    quotas: &Quotas,
+    creator: &Addr,
+) -> Result<(), ContractError> {
+    let mut stats = CREATOR_STATS
+        .may_load(storage, creator)?
+        .unwrap_or_default();
+
+    stats.live_slaves = stats
+        .live_slaves
+        .checked_add(1)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Add, stats.live_slaves, 1))?;
+    if let Some(max) = quotas.max_slaves_per_creator {
+        if stats.live_slaves > max {
+            return Err(ContractError::CreatorQuotaExceeded { max });
+        }
+    }
+
+    if let Some(rate_limit) = &quotas.rate_limit {
+        if env.block.height >= stats.window_start.saturating_add(rate_limit.window_blocks) {
+            stats.window_start = env.block.height;
+            stats.window_deployments = 0;
+        }
+        stats.window_deployments = stats.window_deployments.checked_add(1).ok_or_else(|| {
+            OverflowError::new(OverflowOperation::Add, stats.window_deployments, 1)
+        })?;
+        if stats.window_deployments > rate_limit.max_deployments {
+            return Err(ContractError::RateLimited {
+                max: rate_limit.max_deployments,
+                blocks: rate_limit.window_blocks,
+            });
+        }
+    }
+
+    let live = checked_increment(LIVE_SLAVES.may_load(storage)?.unwrap_or_default())?;
+    if let Some(max) = quotas.max_slaves {
+        if live > max {
+            return Err(ContractError::GlobalQuotaExceeded { max });
+        }
+    }
+
+    CREATOR_STATS.save(storage, creator, &stats)?;
+    LIVE_SLAVES.save(storage, &live)?;
+    Ok(())
+}
+
+/// Refuses code ids whose wasm does not have the expected checksum.
+fn verify_code_checksum(
+    deps: Deps,
+    code_id: u64,
+    expected: &HexBinary,
+) -> Result<(), ContractError> {
+    let code_info = deps.querier.query_wasm_code_info(code_id)?;
+    if &code_info.checksum != expected {
+        return Err(ContractError::ChecksumMismatch { code_id });
+    }
+    Ok(())
+}
+
+/// Charges the fee and takes the forwarded funds of one slave out of
+/// `remaining`, queues the deployment for its reply and returns the
+/// instantiate submessage along with the salt if one is used.
+fn prepare_slave(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    sender: &Addr,
+    remaining: &mut Coins,
+    spec: SlaveSpec,
+    batch: Option<BatchPosition>,
+) -> Result<(SubMsg, Option<Binary>), ContractError> {
+    consume_quota(deps.storage, env, &config.quotas, sender)?;
+
+    // the deployment fee stays in the master treasury
+    for fee in &config.deploy_fee {
+        if remaining.amount_of(&fee.denom) < fee.amount {
+            return Err(ContractError::InsufficientFee {
+                fee: Coins::try_from(config.deploy_fee.clone())?.to_string(),
+            });
+        }
+        remaining.sub(fee.clone())?;
+        FEES.update(deps.storage, &fee.denom, |total| -> StdResult<_> {
+            let mut total: FeeTotal = total.unwrap_or_default();
+            total.collected = total.collected.checked_add(fee.amount)?;
+            Ok(total)
+        })?;
+    }
+
+    let funds = match spec.forward_funds {
+        Some(forward_funds) => funds_to_forward(config, remaining, forward_funds)?,
+        None => Coins::default(),
+    };
+    for coin in funds.iter() {
+        remaining.sub(coin.clone())?;
+    }
+    let funds = funds.into_vec();
+
+    let SlaveTemplate {
+        code_id,
+        checksum,
+        label_prefix,
+        default_msg,
+        ..
+    } = resolve_template(deps.as_ref(), spec.template.as_deref())?;
+    verify_code_checksum(deps.as_ref(), code_id, &checksum)?;
+
+    let seq = checked_increment(SLAVE_SEQ.may_load(deps.storage)?.unwrap_or_default())?;
+    SLAVE_SEQ.save(deps.storage, &seq)?;
+    let label = match spec.label {
+        Some(label) => {
+            validate_label(&label)?;
+            label
+        }
+        None => format!("{}-{}", label_prefix, seq),
+    };
+
+    let admin = match spec.admin.unwrap_or(AdminPolicy::Master {}) {
+        AdminPolicy::Master {} => Some(env.contract.address.clone()),
+        AdminPolicy::Sender {} => Some(sender.clone()),
+        AdminPolicy::Address { address } => Some(deps.api.addr_validate(&address)?),
+        AdminPolicy::None {} => None,
+    };
+
+    let msg = match (spec.count, default_msg) {
+        (Some(count), _) => to_json_binary(&SlaveInstantiateMsg {
+            count,
+            master: env.contract.address.to_string(),
+        })?,
+        (None, Some(default_msg)) => default_msg,
+        (None, None) => return Err(ContractError::MissingInstantiateMsg {}),
+    };
+
+    let salt = match spec.salt {
+        Some(SlaveSalt::Custom { salt }) => Some(scoped_salt(sender, &salt)?),
+        Some(SlaveSalt::Derived {}) => {
+            let salt = derived_salt(deps.as_ref(), sender)?;
+            SALT_NONCES.update(deps.storage, sender, |nonce| -> StdResult<_> {
+                Ok(checked_increment(nonce.unwrap_or_default())?)
+            })?;
+            Some(salt)
+        }
+        None => None,
+    };
+
+    // remembered until the reply tells us the address of the new slave
+    let reply_id = next_reply_id(deps.storage)?;
+    PENDING_DEPLOYMENTS.save(
+        deps.storage,
+        reply_id,
+        &PendingDeployment {
+            creator: sender.clone(),
+            count: spec.count,
+            code_id,
+            template: spec.template,
+            label: label.clone(),
+            admin: admin.clone(),
+            funds: funds.clone(),
+            fee: config.deploy_fee.clone(),
+            batch,
+        },
+    )?;
+
+    let admin = admin.map(Addr::into_string);
+    let instantiate_message: WasmMsg = match &salt {
+        Some(salt) => WasmMsg::Instantiate2 {
+            admin,
+            code_id,
+            label,
+            msg,
+            funds,
+            salt: salt.clone(),
+        },
+        None => WasmMsg::Instantiate {
+            admin,
+            code_id,
+            msg,
+            funds,
+            label,
+        },
+    };
+
+    let sub_msg: SubMsg = SubMsg::reply_always(CosmosMsg::Wasm(instantiate_message), reply_id);
+    Ok((sub_msg, salt))
+}
+
+fn next_reply_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = checked_increment(REPLY_ID.may_load(storage)?.unwrap_or_default())?;
+    REPLY_ID.save(storage, &id)?;
+    Ok(id)
+}
+
+/// `value + 1` for sequences and tallies, failing instead of panicking.
+fn checked_increment(value: u64) -> Result<u64, OverflowError> {
+    value
+        .checked_add(1)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Add, value, 1))
+}
+
+/// Whatever was neither paid as fee nor forwarded goes back to the sender.
+fn refund_remaining(res: Response, sender: &Addr, remaining: Coins) -> Response {
+    if remaining.is_empty() {
+        return res;
+    }
+    res.add_message(BankMsg::Send {
+        to_address: sender.to_string(),
+        amount: remaining.into_vec(),
+    })
+}
+
+/// Loads a registered slave, failing unless `sender` owns it.
+fn load_owned_slave(
+    deps: Deps,
+    sender: &Addr,
+    slave: &str,
+) -> Result<(Addr, SlaveInfo), ContractError> {
+    let slave = deps.api.addr_validate(slave)?;
+    let slave_info =
+        SLAVES
+            .may_load(deps.storage, &slave)?
+            .ok_or_else(|| ContractError::UnknownSlave {
+                address: slave.to_string(),
+            })?;
+    if slave_info.owner != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    match slave_info.status {
+        SlaveStatus::Active => Ok((slave, slave_info)),
+        SlaveStatus::Pending => Err(ContractError::SlaveNotRegistered {
+            address: slave.to_string(),
+        }),
+        SlaveStatus::Archived => Err(ContractError::SlaveArchived {
+            address: slave.to_string(),
+        }),
+    }
+}
+
+/// Confirms the calling slave. The slave calls this from its own
+/// instantiation, usually before the master handled the instantiate reply.
+pub fn register_slave(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let contract_info = deps
+        .querier
+        .query_wasm_contract_info(&info.sender)
+        .map_err(|_| ContractError::UnapprovedSlave {})?;
+    if contract_info.creator != env.contract.address
+        || !is_approved_code_id(deps.as_ref(), contract_info.code_id)?
+    {
+        return Err(ContractError::UnapprovedSlave {});
+    }
+
+    match SLAVES.may_load(deps.storage, &info.sender)? {
+        Some(mut slave_info) => match slave_info.status {
+            SlaveStatus::Archived => {
+                return Err(ContractError::SlaveArchived {
+                    address: info.sender.to_string(),
+                })
+            }
+            SlaveStatus::Pending => {
+                slave_info.status = SlaveStatus::Active;
+                SLAVES.save(deps.storage, &info.sender, &slave_info)?;
+            }
+            SlaveStatus::Active => {}
+        },
+        // picked up once the instantiate reply registers the slave
+        None => EARLY_REGISTRATIONS.save(deps.storage, &info.sender, &env.block.height)?,
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "register_slave")
+        .add_attribute("slave", info.sender.as_str()))
+}
+
+/// Code ids the master deploys slaves from: the configured one and those of
+/// the enabled templates.
+fn is_approved_code_id(deps: Deps, code_id: u64) -> StdResult<bool> {
+    if CONFIG.load(deps.storage)?.slave_code_id == code_id {
+        return Ok(true);
+    }
+    for item in TEMPLATES.range(deps.storage, None, None, Order::Ascending) {
+        let (_, template) = item?;
+        if template.enabled && template.code_id == code_id {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+pub fn decommission_slave(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    slave: String,
+    mode: DecommissionMode,
+) -> Result<Response, ContractError> {
+    let slave = deps.api.addr_validate(&slave)?;
+    let mut slave_info =
+        SLAVES
+            .may_load(deps.storage, &slave)?
+            .ok_or_else(|| ContractError::UnknownSlave {
+                address: slave.to_string(),
+            })?;
+    // the master owner may decommission any slave, registered or not
+    if slave_info.owner != info.sender {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+    }
+    if slave_info.status == SlaveStatus::Archived {
+        return Err(ContractError::SlaveArchived {
+            address: slave.to_string(),
+        });
+    }
+
+    let mut res = Response::new()
+        .add_attribute("method", "decommission_slave")
+        .add_attribute("slave", slave.as_str());
+    if mode != (DecommissionMode::Archive {})
+        && slave_info.admin.as_ref() != Some(&env.contract.address)
+    {
+        return Err(ContractError::NotSlaveAdmin {});
+    }
+    match mode {
+        DecommissionMode::Archive {} => {
+            res = res.add_attribute("mode", "archive");
+        }
+        DecommissionMode::ClearAdmin {} => {
+            slave_info.admin = None;
+            res = res
+                .add_attribute("mode", "clear_admin")
+                .add_message(WasmMsg::ClearAdmin {
+                    contract_addr: slave.to_string(),
+                });
+        }
+        DecommissionMode::HandToOwner {} => {
+            slave_info.admin = Some(slave_info.owner.clone());
+            res = res
+                .add_attribute("mode", "hand_to_owner")
+                .add_message(WasmMsg::UpdateAdmin {
+                    contract_addr: slave.to_string(),
+                    admin: slave_info.owner.to_string(),
+                });
+        }
+    }
+
+    // archived slaves no longer count against the quotas
+    CREATOR_STATS.update(deps.storage, &slave_info.creator, |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.live_slaves = stats.live_slaves.saturating_sub(1);
+        Ok(stats)
+    })?;
+    LIVE_SLAVES.update(deps.storage, |live| -> StdResult<_> {
+        Ok(live.saturating_sub(1))
+    })?;
+    slave_info.status = SlaveStatus::Archived;
+    slave_info.pending_owner = None;
+    SLAVES.save(deps.storage, &slave, &slave_info)?;
+
+    Ok(res)
+}
+
+pub fn transfer_slave(
+    deps: DepsMut,
+    info: MessageInfo,
+    slave: String,
+    new_owner: String,
+    require_accept: Option<bool>,
+) -> Result<Response, ContractError> {
+    let (slave, mut slave_info) = load_owned_slave(deps.as_ref(), &info.sender, &slave)?;
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+
+    let res = Response::new()
+        .add_attribute("method", "transfer_slave")
+        .add_attribute("slave", slave.as_str());
+    if require_accept.unwrap_or(false) {
+        slave_info.pending_owner = Some(new_owner.clone());
+        SLAVES.save(deps.storage, &slave, &slave_info)?;
+        return Ok(res.add_event(
+            Event::new("slave_transfer_offer")
+                .add_attribute("slave", slave.as_str())
+                .add_attribute("from", info.sender.as_str())
+                .add_attribute("to", new_owner.as_str()),
+        ));
+    }
+
+    complete_transfer(deps, res, slave, slave_info, new_owner)
+}
+
+pub fn accept_slave(
+    deps: DepsMut,
+    info: MessageInfo,
+    slave: String,
+) -> Result<Response, ContractError> {
+    let slave = deps.api.addr_validate(&slave)?;
+    let slave_info =
+        SLAVES
+            .may_load(deps.storage, &slave)?
+            .ok_or_else(|| ContractError::UnknownSlave {
+                address: slave.to_string(),
+            })?;
+    if slave_info.pending_owner.as_ref() != Some(&info.sender) {
+        return Err(ContractError::NoPendingTransfer {});
+    }
+
+    let res = Response::new()
+        .add_attribute("method", "accept_slave")
+        .add_attribute("slave", slave.as_str());
+    complete_transfer(deps, res, slave, slave_info, info.sender)
+}
+
+pub fn cancel_slave_transfer(
+    deps: DepsMut,
+    info: MessageInfo,
+    slave: String,
+) -> Result<Response, ContractError> {
+    let (slave, mut slave_info) = load_owned_slave(deps.as_ref(), &info.sender, &slave)?;
+    let pending_owner = slave_info
+        .pending_owner
+        .take()
+        .ok_or(ContractError::NoPendingTransfer {})?;
+    SLAVES.save(deps.storage, &slave, &slave_info)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "cancel_slave_transfer")
+        .add_event(
+            Event::new("slave_transfer_cancel")
+                .add_attribute("slave", slave.as_str())
+                .add_attribute("to", pending_owner.as_str()),
+        ))
+}
+
+/// Moves the slave to its new owner. Saving through `SLAVES` keeps the owner
+/// index in step with the registry.
+fn complete_transfer(
+    deps: DepsMut,
+    res: Response,
+    slave: Addr,
+    mut slave_info: SlaveInfo,
+    new_owner: Addr,
+) -> Result<Response, ContractError> {
+    let previous_owner = std::mem::replace(&mut slave_info.owner, new_owner);
+    slave_info.pending_owner = None;
+    SLAVES.save(deps.storage, &slave, &slave_info)?;
+
+    Ok(res.add_event(
+        Event::new("slave_transfer")
+            .add_attribute("slave", slave.as_str())
+            .add_attribute("from", previous_owner.as_str())
+            .add_attribute("to", slave_info.owner.as_str()),
+    ))
+}
+
+pub fn execute_on_slave(
+    deps: DepsMut,
+    info: MessageInfo,
+    slave: String,
+    msg: Binary,
+    funds: Option<ForwardFunds>,
+    return_data: Option<bool>,
+) -> Result<Response, ContractError> {
+    let (slave, _) = load_owned_slave(deps.as_ref(), &info.sender, &slave)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut remaining = Coins::try_from(info.funds.clone())?;
+    let forwarded = match funds {
+        Some(funds) => funds_to_forward(&config, &remaining, funds)?,
+        None => Coins::default(),
+    };
+    for coin in forwarded.iter() {
+        remaining.sub(coin.clone())?;
+    }
+
+    let execute_msg = WasmMsg::Execute {
+        contract_addr: slave.to_string(),
+        msg,
+        funds: forwarded.into_vec(),
+    };
+    let mut res = Response::new()
+        .add_attribute("method", "execute_on_slave")
+        .add_attribute("slave", slave.as_str());
+    res = if return_data.unwrap_or(false) {
+        let reply_id = next_reply_id(deps.storage)?;
+        PENDING_RELAYS.save(deps.storage, reply_id, &slave)?;
+        res.add_submessage(SubMsg::reply_on_success(execute_msg, reply_id))
+    } else {
+        res.add_message(execute_msg)
+    };
+    Ok(refund_remaining(res, &info.sender, remaining))
+}
+
+pub fn broadcast_to_slaves(
+    deps: DepsMut,
+    info: MessageInfo,
+    action: SlaveExecuteMsg,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let slaves = SLAVES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let msg = to_json_binary(&action)?;
+    let mut res = Response::new().add_attribute("method", "broadcast_to_slaves");
+    let mut targeted = 0u32;
+    for (slave, slave_info) in &slaves {
+        if slave_info.status != SlaveStatus::Active {
+            continue;
+        }
+        res = res.add_message(WasmMsg::Execute {
+            contract_addr: slave.to_string(),
+            msg: msg.clone(),
+            funds: vec![],
+        });
+        targeted += 1;
+    }
+
+    // cursor for the next page
+    let last = if slaves.len() == limit {
+        slaves.last().map(|(slave, _)| slave.clone())
+    } else {
+        None
+    };
+    res = res.add_attribute("targeted", targeted.to_string());
+    if let Some(last) = &last {
+        res = res.add_attribute("last", last.as_str());
+    }
+    Ok(res.set_data(to_json_binary(&BroadcastToSlavesResponse {
+        targeted,
+        last,
+    })?))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn migrate_slaves(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_code_id: u64,
+    checksum: HexBinary,
+    msg: Binary,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    verify_code_checksum(deps.as_ref(), new_code_id, &checksum)?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let slaves = SLAVES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut res = Response::new().add_attribute("method", "migrate_slaves");
+    let mut skipped = 0u32;
+    let mut migrating = 0u32;
+    for (slave, slave_info) in &slaves {
+        // only active slaves administered by the master can be migrated by it
+        if slave_info.status != SlaveStatus::Active
+            || slave_info.admin.as_ref() != Some(&env.contract.address)
+        {
+            skipped += 1;
+            continue;
+        }
+        let reply_id = next_reply_id(deps.storage)?;
+        PENDING_MIGRATIONS.save(
+            deps.storage,
+            reply_id,
+            &PendingMigration {
+                slave: slave.clone(),
+                code_id: new_code_id,
+            },
+        )?;
+        let migrate_msg = WasmMsg::Migrate {
+            contract_addr: slave.to_string(),
+            new_code_id,
+            msg: msg.clone(),
+        };
+        res = res.add_submessage(SubMsg::reply_always(migrate_msg, reply_id));
+        migrating += 1;
+    }
+
+    // cursor for the next page
+    let last = if slaves.len() == limit {
+        slaves.last().map(|(slave, _)| slave.clone())
+    } else {
+        None
+    };
+    res = res
+        .add_attribute("new_code_id", new_code_id.to_string())
+        .add_attribute("migrating", migrating.to_string())
+        .add_attribute("skipped", skipped.to_string());
+    if let Some(last) = &last {
+        res = res.add_attribute("last", last.as_str());
+    }
+    Ok(res.set_data(to_json_binary(&MigrateSlavesResponse {
+        migrating,
+        skipped,
+        last,
+    })?))
+}
+
+/// Looks up the code id, label and default message to deploy a slave with.
+/// Without a template name the configured slave code id is used.
+fn resolve_template(deps: Deps, name: Option<&str>) -> Result<SlaveTemplate, ContractError> {
+    match name {
+        Some(name) => {
+            let template = TEMPLATES.may_load(deps.storage, name)?.ok_or_else(|| {
+                ContractError::UnknownTemplate {
+                    name: name.to_string(),
+                }
+            })?;
+            if !template.enabled {
+                return Err(ContractError::TemplateDisabled {
+                    name: name.to_string(),
+                });
+            }
+            Ok(template)
+        }
+        None => {
+            let config = CONFIG.load(deps.storage)?;
+            Ok(SlaveTemplate {
+                code_id: config.slave_code_id,
+                checksum: config.slave_checksum,
+                label_prefix: DEFAULT_SLAVE_LABEL.to_string(),
+                default_msg: None,
+                enabled: true,
+            })
+        }
+    }
+}
+
+/// Picks the coins to send along with a slave instantiation out of the
+/// available ones. Only allowlisted denoms can be forwarded.
+fn funds_to_forward(
+    config: &Config,
+    available: &Coins,
+    forward_funds: ForwardFunds,
+) -> Result<Coins, ContractError> {
+    let forwarded = match forward_funds {
+        ForwardFunds::All {} => available.clone(),
+        ForwardFunds::Coins { amount } => {
+            let mut forwarded = Coins::default();
+            for coin in amount {
+                forwarded.add(coin)?;
+            }
+            forwarded
+        }
+    };
+
+    for coin in forwarded.iter() {
+        if !config.allowed_denoms.contains(&coin.denom) {
+            return Err(ContractError::UnsupportedDenom {
+                denom: coin.denom.clone(),
+            });
+        }
+        if coin.amount > available.amount_of(&coin.denom) {
+            return Err(ContractError::InsufficientFunds {
+                denom: coin.denom.clone(),
+            });
+        }
+    }
+    Ok(forwarded)
+}
+
+fn validate_label(label: &str) -> Result<(), ContractError> {
+    if !is_valid_label(label, MAX_LABEL_LENGTH) {
+        return Err(ContractError::InvalidLabel {
+            max: MAX_LABEL_LENGTH,
+        });
+    }
+    Ok(())
+}
+
+fn is_valid_label(label: &str, max: usize) -> bool {
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.');
+    !label.is_empty() && label.len() <= max && label.chars().all(valid_char)
+}
+
+/// The salt of a custom-salt deployment: the sha256 hash of the deployer
+/// address followed by their salt, so nobody can take another deployer's address.
+fn scoped_salt(creator: &Addr, salt: &[u8]) -> Result<Binary, ContractError> {
+    if salt.is_empty() || salt.len() > 64 {
+        return Err(ContractError::InvalidSalt {});
+    }
+    let salt = Sha256::new()
+        .chain_update(creator.as_bytes())
+        .chain_update(salt)
+        .finalize();
+    Ok(Binary::from(salt.as_slice()))
+}
+
+/// The next derived salt of a deployer: the sha256 hash of their address
+/// followed by the big endian number of derived-salt deployments made so far.
+fn derived_salt(deps: Deps, creator: &Addr) -> StdResult<Binary> {
+    let nonce = SALT_NONCES
+        .may_load(deps.storage, creator)?
+        .unwrap_or_default();
+    let salt = Sha256::new()
+        .chain_update(creator.as_bytes())
+        .chain_update(nonce.to_be_bytes())
+        .finalize();
+    Ok(Binary::from(salt.as_slice()))
+}
+
+fn predict_slave_address(deps: Deps, env: &Env, code_id: u64, salt: &[u8]) -> StdResult<Addr> {
+    let checksum = deps.querier.query_wasm_code_info(code_id)?.checksum;
+    let creator = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+    let address = instantiate2_address(checksum.as_slice(), &creator, salt)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    deps.api.addr_humanize(&address)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> StdResult<Response> {
+    // pending entries are consumed by their reply, so unknown and already
+    // handled ids are rejected alike
+    if let Some(pending) = PENDING_DEPLOYMENTS.may_load(deps.storage, msg.id)? {
+        PENDING_DEPLOYMENTS.remove(deps.storage, msg.id);
+        return handle_instantiate_reply(deps, env, msg, pending);
+    }
+    if let Some(pending) = PENDING_MIGRATIONS.may_load(deps.storage, msg.id)? {
+        PENDING_MIGRATIONS.remove(deps.storage, msg.id);
+        return handle_migrate_reply(deps, env, msg, pending);
+    }
+    if let Some(slave) = PENDING_RELAYS.may_load(deps.storage, msg.id)? {
+        PENDING_RELAYS.remove(deps.storage, msg.id);
+        return handle_relay_reply(msg, slave);
+    }
+
+    Err(StdError::generic_err(format!(
+        "Unknown reply id: {}",
+        msg.id
+    )))
+}
+
+/// Passes the data set by a slave on to the caller of `ExecuteOnSlave`.
+pub fn handle_relay_reply(msg: Reply, slave: Addr) -> StdResult<Response> {
+    let res = Response::new()
+        .add_attribute("method", "handle_relay_reply")
+        .add_attribute("slave", slave.as_str());
+    let data = match msg.result.into_result() {
+        Ok(response) => response.data,
+        Err(err) => return Err(StdError::generic_err(err)),
+    };
+    // the execute response wraps the slave's own data
+    let data = match data {
+        Some(data) => {
+            parse_execute_response_data(&data)
+                .map_err(|err| StdError::generic_err(err.to_string()))?
+                .data
+        }
+        None => None,
+    };
+    Ok(match data {
+        Some(data) => res.set_data(data),
+        None => res,
+    })
+}
+
+/// Records the outcome of a slave migration. Failures are kept in the
+/// registry instead of reverting the migration of the other slaves.
+pub fn handle_migrate_reply(
+    deps: DepsMut,
+    env: Env,
+    msg: Reply,
+    pending: PendingMigration,
+) -> StdResult<Response> {
+    let mut slave_info = SLAVES.load(deps.storage, &pending.slave)?;
+    let error = match msg.result {
+        SubMsgResult::Ok(_) => {
+            slave_info.code_id = pending.code_id;
+            None
+        }
+        SubMsgResult::Err(err) => Some(err),
+    };
+    let res = Response::new()
+        .add_attribute("method", "handle_migrate_reply")
+        .add_attribute("slave", pending.slave.as_str())
+        .add_attribute("success", error.is_none().to_string());
+
+    slave_info.last_migration = Some(MigrationRecord {
+        code_id: pending.code_id,
+        height: env.block.height,
+        error,
+    });
+    SLAVES.save(deps.storage, &pending.slave, &slave_info)?;
+
+    Ok(res)
+}
+
+/// Finds the slave address in the `instantiate` event of the reply.
+fn contract_address_from_events(events: &[Event]) -> StdResult<String> {
+    // Find the event type "instantiate" which contains the contract_address
+    let event = events
+        .iter()
+        .find(|event| event.ty == "instantiate")
+        .ok_or_else(|| StdError::generic_err("Cannot find `instantiate` event"))?;
+
+    // Find the contract_address from the "instantiate" event
+    event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "_contract_address")
+        .map(|attr| attr.value.clone())
+        .ok_or_else(|| StdError::generic_err("Cannot find `_contract_address` attribute"))
+}
+
+pub fn handle_instantiate_reply(
+    deps: DepsMut,
+    env: Env,
+    msg: Reply,
+    pending: PendingDeployment,
+) -> StdResult<Response> {
+    // Ensure the result is parsed correctly
+    let result = match msg.result {
+        SubMsgResult::Ok(result) => result,
+        SubMsgResult::Err(err) => {
+            let config = CONFIG.load(deps.storage)?;
+            if config.failure_policy == FailurePolicy::Revert {
+                return Err(StdError::generic_err(format!("SubMsg error: {}", err)));
+            }
+            return handle_failed_deployment(deps, env, msg.id, pending, err);
+        }
+    };
+
+    // the protobuf encoded MsgInstantiateContractResponse carries the address,
+    // the events are only used when it is missing or cannot be decoded
+    let (contract_address, slave_data) = match result
+        .data
+        .as_ref()
+        .map(|data| parse_instantiate_response_data(data))
+    {
+        Some(Ok(response)) if !response.contract_address.is_empty() => {
+            (response.contract_address, response.data)
+        }
+        _ => (contract_address_from_events(&result.events)?, None),
+    };
+
+    let contract_address = deps.api.addr_validate(&contract_address)?;
+    let status = if EARLY_REGISTRATIONS.has(deps.storage, &contract_address) {
+        EARLY_REGISTRATIONS.remove(deps.storage, &contract_address);
+        SlaveStatus::Active
+    } else {
+        SlaveStatus::Pending
+    };
+    SLAVES.save(
+        deps.storage,
+        &contract_address,
+        &SlaveInfo {
+            owner: pending.creator.clone(),
+            pending_owner: None,
+            creator: pending.creator,
+            initial_count: pending.count,
+            code_id: pending.code_id,
+            template: pending.template,
+            label: pending.label,
+            admin: pending.admin,
+            height: env.block.height,
+            time: env.block.time,
+            last_migration: None,
+            status,
+        },
+    )?;
+
+    // Construct the response and include relevant attributes
+    let mut res = Response::new()
+        .add_attribute("method", "handle_instantiate_reply")
+        .add_attribute("contract_address", contract_address.as_str());
+    if let Some(slave_data) = slave_data {
+        res = res.add_attribute("slave_data", slave_data.to_base64());
+    }
+
+    match pending.batch {
+        Some(batch) => collect_batch_slave(deps.storage, res, batch, Some(contract_address)),
+        None => Ok(res),
+    }
+}
+
+/// Adds a slave to its batch. The last reply of a batch hands all new
+/// addresses back to the caller.
+fn collect_batch_slave(
+    storage: &mut dyn Storage,
+    res: Response,
+    batch: BatchPosition,
+    slave: Option<Addr>,
+) -> StdResult<Response> {
+    let mut slaves = BATCH_SLAVES
+        .may_load(storage, batch.id)?
+        .unwrap_or_default();
+    slaves.extend(slave);
+    if batch.index + 1 == batch.size {
+        BATCH_SLAVES.remove(storage, batch.id);
+        return Ok(res.set_data(to_json_binary(&DeploySlavesResponse { slaves })?));
+    }
+    BATCH_SLAVES.save(storage, batch.id, &slaves)?;
+    Ok(res)
+}
+
+/// Records a failed instantiation and sends fee and funds back to the
+/// requester instead of reverting the transaction.
+fn handle_failed_deployment(
+    deps: DepsMut,
+    env: Env,
+    id: u64,
+    pending: PendingDeployment,
+    error: String,
+) -> StdResult<Response> {
+    for fee in &pending.fee {
+        FEES.update(deps.storage, &fee.denom, |total| -> StdResult<_> {
+            let mut total: FeeTotal = total.unwrap_or_default();
+            total.collected = total.collected.checked_sub(fee.amount)?;
+            Ok(total)
+        })?;
+    }
+    // the slave never went live
+    CREATOR_STATS.update(deps.storage, &pending.creator, |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.live_slaves = stats.live_slaves.saturating_sub(1);
+        Ok(stats)
+    })?;
+    LIVE_SLAVES.update(deps.storage, |live| -> StdResult<_> {
+        Ok(live.saturating_sub(1))
+    })?;
+
+    let mut refunded = Coins::default();
+    for coin in pending.fee.into_iter().chain(pending.funds) {
+        refunded.add(coin)?;
+    }
+    let refunded = refunded.into_vec();
+    FAILED_DEPLOYMENTS.save(
+        deps.storage,
+        id,
+        &FailedDeployment {
+            requester: pending.creator.clone(),
+            code_id: pending.code_id,
+            label: pending.label,
+            error: error.clone(),
+            height: env.block.height,
+            refunded: refunded.clone(),
+        },
+    )?;
+
+    let mut res = Response::new()
+        .add_attribute("method", "handle_failed_deployment")
+        .add_attribute("failed_deployment", id.to_string())
+        .add_attribute("error", error);
+    if !refunded.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: pending.creator.to_string(),
+            amount: refunded,
+        });
+    }
+
+    match pending.batch {
+        Some(batch) => collect_batch_slave(deps.storage, res, batch, None),
+        None => Ok(res),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::RateLimit;
+    use cosmwasm_std::testing::{
+        mock_dependencies_with_balance, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+        MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{
+        coin, coins, from_json, Attribute, CodeInfoResponse, ContractInfoResponse, ContractResult,
+        OwnedDeps, QuerierResult, SubMsgResponse, SystemError, SystemResult, WasmQuery,
+    };
+
+    const SLAVE_CODE_ID: u64 = 9552;
+    const SLAVE_CHECKSUM: [u8; 32] = [0xab; 32];
+
+    fn default_instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            count: 17,
+            slave_code_id: SLAVE_CODE_ID,
+            slave_checksum: HexBinary::from(SLAVE_CHECKSUM),
+            allowed_denoms: vec![],
+            deploy_fee: vec![],
+            max_batch_size: 10,
+            quotas: Quotas::default(),
+            failure_policy: FailurePolicy::Revert,
+            count_bounds: CountBounds::default(),
+        }
+    }
+
+    fn instantiate_reply(id: u64, contract_address: &str) -> Reply {
+        Reply {
+            id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("instantiate")
+                    .add_attribute("_contract_address", contract_address)
+                    .add_attribute("code_id", SLAVE_CODE_ID.to_string())],
+                data: None,
+            }),
+        }
+    }
+
+    /// Reports every code as having `SLAVE_CHECKSUM` and every contract as
+    /// instantiated by the master from `SLAVE_CODE_ID`.
+    fn slave_code_querier(query: &WasmQuery) -> QuerierResult {
+        match query {
+            WasmQuery::CodeInfo { code_id } => {
+                let mut info = CodeInfoResponse::default();
+                info.code_id = *code_id;
+                info.checksum = HexBinary::from(SLAVE_CHECKSUM);
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&info).unwrap()))
+            }
+            WasmQuery::ContractInfo { .. } => {
+                let mut info = ContractInfoResponse::default();
+                info.code_id = SLAVE_CODE_ID;
+                info.creator = MOCK_CONTRACT_ADDR.to_string();
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&info).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::Unknown {}),
+        }
+    }
+
+    fn mock_deps() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        deps.querier.update_wasm(slave_code_querier);
+        deps
+    }
+
+    /// Lets `slave` confirm itself.
+    fn register_slave(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, slave: &str) {
+        deps.querier.update_wasm(slave_code_querier);
+        let info = mock_info(slave, &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::RegisterSlave {},
+        )
+        .unwrap();
+    }
+
+    /// Protobuf encoded `MsgInstantiateContractResponse`, field 1 is the
+    /// address and field 2 the data returned by the slave.
+    fn instantiate_response_data(contract_address: &str, data: &[u8]) -> Binary {
+        let mut bytes = vec![0x0a, contract_address.len() as u8];
+        bytes.extend_from_slice(contract_address.as_bytes());
+        if !data.is_empty() {
+            bytes.extend_from_slice(&[0x12, data.len() as u8]);
+            bytes.extend_from_slice(data);
+        }
+        Binary::from(bytes)
+    }
+
+    fn data_reply(id: u64, data: Binary, events: Vec<Event>) -> Reply {
+        Reply {
+            id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events,
+                data: Some(data),
+            }),
+        }
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let value: CountResponse = from_json(&res).unwrap();
+        assert_eq!(17, value.count);
+    }
+
+    #[test]
+    fn increment() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // beneficiary can release it
+        let info = mock_info("anyone", &coins(2, "token"));
+        let msg = ExecuteMsg::Increment {};
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // should increase counter by 1
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let value: CountResponse = from_json(&res).unwrap();
+        assert_eq!(18, value.count);
+    }
+
+    #[test]
+    fn counter_bounds() {
+        let mut deps = mock_deps();
+
+        let msg = InstantiateMsg {
+            count: i32::MAX,
+            ..default_instantiate_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // overflowing returns an error instead of aborting
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Increment {});
+        match res {
+            Err(ContractError::CounterOverflow {}) => {}
+            _ => panic!("Must return counter overflow error"),
+        }
+
+        let bounds = |min, max| ExecuteMsg::UpdateCountBounds {
+            bounds: CountBounds { min, max },
+        };
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::Set { value: 0 };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, bounds(Some(1), Some(-1)));
+        match res {
+            Err(ContractError::InvalidCountBounds {}) => {}
+            _ => panic!("Must return invalid count bounds error"),
+        }
+        // the current count has to be within the new bounds
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, bounds(Some(1), None));
+        match res {
+            Err(ContractError::CounterOutOfBounds { count: 0 }) => {}
+            _ => panic!("Must return counter out of bounds error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        execute(deps.as_mut(), mock_env(), info, bounds(Some(-1), Some(1))).unwrap();
+        let info = mock_info("anyone", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Increment {}).unwrap();
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Increment {});
+        match res {
+            Err(ContractError::CounterOutOfBounds { count: 2 }) => {}
+            _ => panic!("Must return counter out of bounds error"),
+        }
+        let info = mock_info("creator", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Set { value: -5 },
+        );
+        match res {
+            Err(ContractError::CounterOutOfBounds { count: -5 }) => {}
+            _ => panic!("Must return counter out of bounds error"),
+        }
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let value: CountResponse = from_json(&res).unwrap();
+        assert_eq!(1, value.count);
+    }
+
+    #[test]
+    fn counter_operations() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let count = |deps: Deps| {
+            let res = query(deps, mock_env(), QueryMsg::GetCount {}).unwrap();
+            let value: CountResponse = from_json(&res).unwrap();
+            value.count
+        };
+
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Decrement {}).unwrap();
+        assert_eq!(16, count(deps.as_ref()));
+        assert_eq!(
+            vec![Event::new("counter")
+                .add_attribute("old", "17")
+                .add_attribute("new", "16")],
+            res.events
+        );
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::Add { amount: -20 };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(-4, count(deps.as_ref()));
+
+        // set and reset are reserved to the owner
+        for msg in [ExecuteMsg::Set { value: 5 }, ExecuteMsg::Reset { count: 5 }] {
+            let info = mock_info("anyone", &[]);
+            let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+            match res {
+                Err(ContractError::Unauthorized {}) => {}
+                _ => panic!("Must return unauthorized error"),
+            }
+            assert_eq!(-4, count(deps.as_ref()));
+        }
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::Set { value: 42 };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(42, count(deps.as_ref()));
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::Reset { count: 0 };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, count(deps.as_ref()));
+        assert_eq!(
+            vec![Event::new("counter")
+                .add_attribute("old", "42")
+                .add_attribute("new", "0")],
+            res.events
+        );
+    }
+
+    #[test]
+    fn deploy_slave_registers_on_reply() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("deployer", &[]);
+        let msg = ExecuteMsg::from(SlaveSpec {
+            count: Some(5),
+            ..Default::default()
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        let env = mock_env();
+        reply(deps.as_mut(), env.clone(), instantiate_reply(1, "slave1")).unwrap();
+        register_slave(&mut deps, "slave1");
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Slave {
+                address: "slave1".to_string(),
+            },
+        )
+        .unwrap();
+        let value: SlaveResponse = from_json(&res).unwrap();
+        assert_eq!(
+            SlaveResponse {
+                address: Addr::unchecked("slave1"),
+                creator: Addr::unchecked("deployer"),
+                owner: Addr::unchecked("deployer"),
+                pending_owner: None,
+                initial_count: Some(5),
+                code_id: SLAVE_CODE_ID,
+                template: None,
+                label: "DeployedSlave-1".to_string(),
+                admin: Some(env.contract.address.clone()),
+                height: env.block.height,
+                time: env.block.time,
+                last_migration: None,
+                status: SlaveStatus::Active,
+            },
+            value
+        );
+
+        // replies without a pending deployment are rejected, including repeated ones
+        reply(deps.as_mut(), mock_env(), instantiate_reply(1, "slave2")).unwrap_err();
+        reply(deps.as_mut(), mock_env(), instantiate_reply(2, "slave2")).unwrap_err();
+    }
+
+    #[test]
+    fn list_slaves_paginates() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for (id, slave) in (1..).zip(["slave1", "slave2", "slave3"]) {
+            let info = mock_info("deployer", &[]);
+            let msg = ExecuteMsg::from(SlaveSpec {
+                count: Some(id as i32 - 1),
+                ..Default::default()
+            });
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+            reply(deps.as_mut(), mock_env(), instantiate_reply(id, slave)).unwrap();
+        }
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ListSlaves {
+                start_after: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap();
+        let value: SlavesResponse = from_json(&res).unwrap();
+        let addresses: Vec<_> = value.slaves.iter().map(|s| s.address.as_str()).collect();
+        assert_eq!(vec!["slave1", "slave2"], addresses);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ListSlaves {
+                start_after: Some("slave2".to_string()),
+                limit: None,
+            },
+        )
+        .unwrap();
+        let value: SlavesResponse = from_json(&res).unwrap();
+        let addresses: Vec<_> = value.slaves.iter().map(|s| s.address.as_str()).collect();
+        assert_eq!(vec!["slave3"], addresses);
+        assert_eq!(Some(2), value.slaves[0].initial_count);
+    }
+
+    #[test]
+    fn update_slave_code_id() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only the owner can change the code id
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::UpdateSlaveCodeId {
+            code_id: 42,
+            checksum: HexBinary::from(SLAVE_CHECKSUM),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::UpdateSlaveCodeId {
+            code_id: 42,
+            checksum: HexBinary::from(SLAVE_CHECKSUM),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let value: ConfigResponse = from_json(&res).unwrap();
+        assert_eq!(42, value.slave_code_id);
+        assert_eq!(Addr::unchecked("creator"), value.owner);
+
+        // new deployments use the updated code id
+        let info = mock_info("deployer", &[]);
+        let msg = ExecuteMsg::from(SlaveSpec {
+            count: Some(1),
+            ..Default::default()
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Instantiate { code_id, .. }) => assert_eq!(42, *code_id),
+            msg => panic!("Unexpected message: {:?}", msg),
+        }
+
+        // a code id whose wasm does not match its checksum is not deployed
+        let info = mock_info("creator", &[]);
+        let update = ExecuteMsg::UpdateSlaveCodeId {
+            code_id: 43,
+            checksum: HexBinary::from([0u8; 32]),
+        };
+        execute(deps.as_mut(), mock_env(), info, update).unwrap();
+        let info = mock_info("deployer", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(matches!(
+            res,
+            Err(ContractError::ChecksumMismatch { code_id: 43 })
+        ));
+    }
+
+    #[test]
+    fn deploy_from_template() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let default_msg = to_json_binary(&SlaveInstantiateMsg {
+            count: 100,
+            master: MOCK_CONTRACT_ADDR.to_string(),
+        })
+        .unwrap();
+        let set_template = |enabled| ExecuteMsg::SetTemplate {
+            name: "big".to_string(),
+            code_id: 77,
+            checksum: HexBinary::from(SLAVE_CHECKSUM),
+            label_prefix: "BigSlave".to_string(),
+            default_msg: Some(default_msg.clone()),
+            enabled,
+        };
+
+        // only the owner manages templates
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, set_template(true));
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // names and label prefixes have to make valid default labels
+        let template = |name: &str, label_prefix: String| ExecuteMsg::SetTemplate {
+            name: name.to_string(),
+            code_id: 77,
+            checksum: HexBinary::from(SLAVE_CHECKSUM),
+            label_prefix,
+            default_msg: None,
+            enabled: true,
+        };
+        let info = mock_info("creator", &[]);
+        let msg = template("", "BigSlave".to_string());
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(matches!(res, Err(ContractError::InvalidTemplateName {})));
+        let too_long = "a".repeat(MAX_LABEL_PREFIX_LENGTH + 1);
+        for label_prefix in ["Big/Slave".to_string(), String::new(), too_long] {
+            let info = mock_info("creator", &[]);
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                template("big", label_prefix),
+            );
+            assert!(matches!(res, Err(ContractError::InvalidLabelPrefix { .. })));
+        }
+
+        // default messages have to point the slaves back to this master
+        for default_msg in [
+            Binary::from(b"not json"),
+            Binary::from(br#"{"count":100}"#),
+            to_json_binary(&SlaveInstantiateMsg {
+                count: 100,
+                master: "other".to_string(),
+            })
+            .unwrap(),
+        ] {
+            let info = mock_info("creator", &[]);
+            let msg = ExecuteMsg::SetTemplate {
+                name: "big".to_string(),
+                code_id: 77,
+                checksum: HexBinary::from(SLAVE_CHECKSUM),
+                label_prefix: "BigSlave".to_string(),
+                default_msg: Some(default_msg),
+                enabled: true,
+            };
+            let res = execute(deps.as_mut(), mock_env(), info, msg);
+            assert!(matches!(res, Err(ContractError::InvalidDefaultMsg {})));
+        }
+
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, set_template(true)).unwrap();
+
+        // without a count the template default message is used
+        let info = mock_info("deployer", &[]);
+        let msg = ExecuteMsg::from(SlaveSpec {
+            template: Some("big".to_string()),
+            ..Default::default()
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                code_id,
+                msg,
+                label,
+                ..
+            }) => {
+                assert_eq!(77, *code_id);
+                assert_eq!(&default_msg, msg);
+                assert_eq!("BigSlave-1", label);
+            }
+            msg => panic!("Unexpected message: {:?}", msg),
+        }
+
+        // the configured code id has no default message to fall back to
+        let info = mock_info("deployer", &[]);
+        let msg = ExecuteMsg::from(SlaveSpec::default());
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::MissingInstantiateMsg {}) => {}
+            _ => panic!("Must return missing instantiate msg error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, set_template(false)).unwrap();
+
+        let info = mock_info("deployer", &[]);
+        let msg = ExecuteMsg::from(SlaveSpec {
+            template: Some("big".to_string()),
+            count: Some(1),
+            ..Default::default()
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::TemplateDisabled { name }) => assert_eq!("big", name),
+            _ => panic!("Must return template disabled error"),
+        }
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Templates {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let value: TemplatesResponse = from_json(&res).unwrap();
+        assert_eq!(1, value.templates.len());
+        assert_eq!("big", value.templates[0].name);
+        assert!(!value.templates[0].enabled);
+    }
+
+    #[test]
+    fn deploy_slave_with_salt() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("deployer", &[]);
+        let msg = ExecuteMsg::from(SlaveSpec {
+            count: Some(1),
+            salt: Some(SlaveSalt::Custom {
+                salt: Binary::from(b"my salt"),
+            }),
+            ..Default::default()
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // custom salts are scoped to the deployer
+        let expected = Sha256::new()
+            .chain_update(b"deployer")
+            .chain_update(b"my salt")
+            .finalize();
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Instantiate2 { salt, .. }) => {
+                assert_eq!(expected.as_slice(), salt.as_slice())
+            }
+            msg => panic!("Unexpected message: {:?}", msg),
+        }
+
+        // derived salts differ for every deployment of the same deployer
+        let mut salts = vec![];
+        for _ in 0..2 {
+            let info = mock_info("deployer", &[]);
+            let msg = ExecuteMsg::from(SlaveSpec {
+                count: Some(1),
+                salt: Some(SlaveSalt::Derived {}),
+                ..Default::default()
+            });
+            let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+            match &res.messages[0].msg {
+                CosmosMsg::Wasm(WasmMsg::Instantiate2 { salt, .. }) => salts.push(salt.clone()),
+                msg => panic!("Unexpected message: {:?}", msg),
+            }
+        }
+        assert_ne!(salts[0], salts[1]);
+
+        let info = mock_info("deployer", &[]);
+        let msg = ExecuteMsg::from(SlaveSpec {
+            count: Some(1),
+            salt: Some(SlaveSalt::Custom {
+                salt: Binary::from(vec![0u8; 65]),
+            }),
+            ..Default::default()
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::InvalidSalt {}) => {}
+            _ => panic!("Must return invalid salt error"),
+        }
+    }
+
+    #[test]
+    fn deploy_slave_forwards_funds() {
+        let mut deps = mock_deps();
+
+        let msg = InstantiateMsg {
+            allowed_denoms: vec!["token".to_string()],
+            ..default_instantiate_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let deploy = |forward_funds| {
+            ExecuteMsg::from(SlaveSpec {
+                count: Some(1),
+                forward_funds: Some(forward_funds),
+                ..Default::default()
+            })
+        };
+
+        let info = mock_info("deployer", &coins(10, "token"));
+        let msg = deploy(ForwardFunds::Coins {
+            amount: coins(4, "token"),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Instantiate { funds, .. }) => {
+                assert_eq!(&coins(4, "token"), funds)
+            }
+            msg => panic!("Unexpected message: {:?}", msg),
+        }
+
+        let info = mock_info("deployer", &coins(10, "token"));
+        let msg = deploy(ForwardFunds::Coins {
+            amount: coins(11, "token"),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::InsufficientFunds { denom }) => assert_eq!("token", denom),
+            _ => panic!("Must return insufficient funds error"),
+        }
+
+        let info = mock_info("deployer", &[coin(10, "token"), coin(3, "earth")]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            deploy(ForwardFunds::All {}),
+        );
+        match res {
+            Err(ContractError::UnsupportedDenom { denom }) => assert_eq!("earth", denom),
+            _ => panic!("Must return unsupported denom error"),
+        }
+    }
+
+    #[test]
+    fn replies_are_matched_by_id() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let spec = |count| SlaveSpec {
+            count: Some(count),
+            ..Default::default()
+        };
+        let info = mock_info("deployer", &[]);
+        let msg = ExecuteMsg::DeploySlaves {
+            slaves: vec![spec(1), spec(2)],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let ids: Vec<_> = res.messages.iter().map(|msg| msg.id).collect();
+        assert_eq!(2, ids.len());
+        assert_ne!(ids[0], ids[1]);
+
+        // each reply registers the slave of its own deployment
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            instantiate_reply(ids[1], "slave2"),
+        )
+        .unwrap();
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            instantiate_reply(ids[0], "slave1"),
+        )
+        .unwrap();
+        for (address, count) in [("slave1", 1), ("slave2", 2)] {
+            let slave = SLAVES
+                .load(&deps.storage, &Addr::unchecked(address))
+                .unwrap();
+            assert_eq!(Some(count), slave.initial_count);
+        }
+
+        // pending deployments are consumed by their reply
+        assert!(PENDING_DEPLOYMENTS.is_empty(&deps.storage));
+        let err = reply(
+            deps.as_mut(),
+            mock_env(),
+            instantiate_reply(ids[0], "slave1"),
+        );
+        assert_eq!(
+            StdError::generic_err(format!("Unknown reply id: {}", ids[0])),
+            err.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn deploy_slave_label_and_admin() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let deploy = |label: Option<&str>, admin| {
+            ExecuteMsg::from(SlaveSpec {
+                count: Some(1),
+                label: label.map(str::to_string),
+                admin: Some(admin),
+                ..Default::default()
+            })
+        };
+        let instantiated = |res: Response| match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Instantiate { label, admin, .. }) => {
+                (label.clone(), admin.clone())
+            }
+            msg => panic!("Unexpected message: {:?}", msg),
+        };
+
+        let info = mock_info("deployer", &[]);
+        let msg = deploy(Some("my slave"), AdminPolicy::Sender {});
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            ("my slave".to_string(), Some("deployer".to_string())),
+            instantiated(res)
+        );
+
+        // default labels carry the sequence number of the deployment
+        let info = mock_info("deployer", &[]);
+        let msg = deploy(
+            None,
+            AdminPolicy::Address {
+                address: "operator".to_string(),
+            },
+        );
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            ("DeployedSlave-2".to_string(), Some("operator".to_string())),
+            instantiated(res)
+        );
+
+        let info = mock_info("deployer", &[]);
+        let msg = deploy(None, AdminPolicy::None {});
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(("DeployedSlave-3".to_string(), None), instantiated(res));
+
+        for label in ["", "slave<script>", &"x".repeat(MAX_LABEL_LENGTH + 1)] {
+            let info = mock_info("deployer", &[]);
+            let msg = deploy(Some(label), AdminPolicy::Master {});
+            let res = execute(deps.as_mut(), mock_env(), info, msg);
+            match res {
+                Err(ContractError::InvalidLabel { .. }) => {}
+                _ => panic!("Must return invalid label error"),
+            }
+        }
+    }
+
+    #[test]
+    fn transfer_slave() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::from(SlaveSpec {
+            count: Some(1),
+            ..Default::default()
+        });
+        let info = mock_info("deployer", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        reply(deps.as_mut(), mock_env(), instantiate_reply(1, "slave1")).unwrap();
+        register_slave(&mut deps, "slave1");
+
+        let owned_by = |deps: Deps, owner: &str| {
+            let res = query(
+                deps,
+                mock_env(),
+                QueryMsg::SlavesByOwner {
+                    owner: owner.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+            let value: SlavesResponse = from_json(&res).unwrap();
+            value
+                .slaves
+                .into_iter()
+                .map(|slave| slave.address.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["slave1"], owned_by(deps.as_ref(), "deployer"));
+
+        let transfer = |new_owner: &str, require_accept| ExecuteMsg::TransferSlave {
+            slave: "slave1".to_string(),
+            new_owner: new_owner.to_string(),
+            require_accept,
+        };
+
+        // only the owner may transfer
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, transfer("anyone", None));
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        let info = mock_info("deployer", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, transfer("alice", None)).unwrap();
+        assert_eq!(
+            vec![Event::new("slave_transfer")
+                .add_attribute("slave", "slave1")
+                .add_attribute("from", "deployer")
+                .add_attribute("to", "alice")],
+            res.events
+        );
+        assert!(owned_by(deps.as_ref(), "deployer").is_empty());
+        assert_eq!(vec!["slave1"], owned_by(deps.as_ref(), "alice"));
+
+        // two-step transfers wait for the new owner
+        let info = mock_info("alice", &[]);
+        execute(deps.as_mut(), mock_env(), info, transfer("bob", Some(true))).unwrap();
+        assert_eq!(vec!["slave1"], owned_by(deps.as_ref(), "alice"));
+
+        let accept = ExecuteMsg::AcceptSlave {
+            slave: "slave1".to_string(),
+        };
+        let info = mock_info("carol", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, accept.clone());
+        match res {
+            Err(ContractError::NoPendingTransfer {}) => {}
+            _ => panic!("Must return no pending transfer error"),
+        }
+
+        let info = mock_info("bob", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, accept.clone()).unwrap();
+        assert_eq!("slave_transfer", res.events[0].ty);
+        assert!(owned_by(deps.as_ref(), "alice").is_empty());
+        assert_eq!(vec!["slave1"], owned_by(deps.as_ref(), "bob"));
+
+        // a cancelled offer can no longer be accepted
+        let info = mock_info("bob", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            transfer("carol", Some(true)),
+        )
+        .unwrap();
+        let cancel = ExecuteMsg::CancelSlaveTransfer {
+            slave: "slave1".to_string(),
+        };
+        let info = mock_info("bob", &[]);
+        execute(deps.as_mut(), mock_env(), info, cancel).unwrap();
+        let info = mock_info("carol", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, accept);
+        match res {
+            Err(ContractError::NoPendingTransfer {}) => {}
+            _ => panic!("Must return no pending transfer error"),
+        }
+        assert_eq!(vec!["slave1"], owned_by(deps.as_ref(), "bob"));
+    }
+
+    #[test]
+    fn deployment_quotas() {
+        let mut deps = mock_deps();
+
+        let msg = InstantiateMsg {
+            quotas: Quotas {
+                max_slaves_per_creator: Some(2),
+                rate_limit: Some(RateLimit {
+                    max_deployments: 1,
+                    window_blocks: 10,
+                }),
+                max_slaves: Some(3),
+            },
+            ..default_instantiate_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let deploy = ExecuteMsg::from(SlaveSpec {
+            count: Some(1),
+            ..Default::default()
+        });
+        let at_height = |height: u64| {
+            let mut env = mock_env();
+            env.block.height = height;
+            env
+        };
+        let start = mock_env().block.height;
+
+        let info = mock_info("deployer", &[]);
+        execute(deps.as_mut(), at_height(start), info, deploy.clone()).unwrap();
+        reply(deps.as_mut(), mock_env(), instantiate_reply(1, "slave1")).unwrap();
+
+        let info = mock_info("deployer", &[]);
+        let res = execute(deps.as_mut(), at_height(start + 9), info, deploy.clone());
+        match res {
+            Err(ContractError::RateLimited { max: 1, blocks: 10 }) => {}
+            _ => panic!("Must return rate limited error"),
+        }
+
+        let res = query(
+            deps.as_ref(),
+            at_height(start + 9),
+            QueryMsg::Quota {
+                address: "deployer".to_string(),
+            },
+        )
+        .unwrap();
+        let value: QuotaResponse = from_json(&res).unwrap();
+        assert_eq!(
+            QuotaResponse {
+                live_slaves: 1,
+                remaining_slaves: Some(1),
+                remaining_in_window: Some(0),
+                window_resets_at: Some(start + 10),
+                remaining_global: Some(2),
+            },
+            value
+        );
+
+        // a new window opens after the configured number of blocks
+        let info = mock_info("deployer", &[]);
+        execute(deps.as_mut(), at_height(start + 10), info, deploy.clone()).unwrap();
+
+        let info = mock_info("deployer", &[]);
+        let res = execute(deps.as_mut(), at_height(start + 20), info, deploy.clone());
+        match res {
+            Err(ContractError::CreatorQuotaExceeded { max: 2 }) => {}
+            _ => panic!("Must return creator quota error"),
+        }
+
+        let info = mock_info("other", &[]);
+        execute(deps.as_mut(), at_height(start), info, deploy.clone()).unwrap();
+        let info = mock_info("another", &[]);
+        let res = execute(deps.as_mut(), at_height(start), info, deploy.clone());
+        match res {
+            Err(ContractError::GlobalQuotaExceeded { max: 3 }) => {}
+            _ => panic!("Must return global quota error"),
+        }
+
+        // archived slaves free up their slot
+        let info = mock_info("deployer", &[]);
+        let msg = ExecuteMsg::DecommissionSlave {
+            slave: "slave1".to_string(),
+            mode: DecommissionMode::Archive {},
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("deployer", &[]);
+        execute(deps.as_mut(), at_height(start + 20), info, deploy).unwrap();
+    }
+
+    #[test]
+    fn instantiate_reply_address_sources() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::DeploySlaves {
+            slaves: (0..4)
+                .map(|count| SlaveSpec {
+                    count: Some(count),
+                    ..Default::default()
+                })
+                .collect(),
+        };
+        let info = mock_info("deployer", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let ids: Vec<u64> = res.messages.iter().map(|msg| msg.id).collect();
+        let event = |address: &str| {
+            vec![Event::new("instantiate").add_attribute("_contract_address", address)]
+        };
+
+        // the response data is preferred over the events
+        let data = instantiate_response_data("slave0", b"hello");
+        let res = reply(deps.as_mut(), mock_env(), data_reply(ids[0], data, vec![])).unwrap();
+        assert!(res.attributes.contains(&Attribute::new(
+            "slave_data",
+            Binary::from(b"hello").to_base64()
+        )));
+        let data = instantiate_response_data("slave1", b"");
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            data_reply(ids[1], data, event("renamed")),
+        )
+        .unwrap();
+
+        // events are used when the data cannot be decoded
+        let data = Binary::from(b"not protobuf");
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            data_reply(ids[2], data, event("slave2")),
+        )
+        .unwrap();
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            instantiate_reply(ids[3], "slave3"),
+        )
+        .unwrap();
+
+        for (address, count) in [("slave0", 0), ("slave1", 1), ("slave2", 2), ("slave3", 3)] {
+            let slave = SLAVES
+                .load(&deps.storage, &Addr::unchecked(address))
+                .unwrap();
+            assert_eq!(Some(count), slave.initial_count);
+        }
+        assert!(!SLAVES.has(&deps.storage, &Addr::unchecked("renamed")));
+    }
+
+    #[test]
+    fn slaves_register_themselves() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::DeploySlaves {
+            slaves: (0..2)
+                .map(|count| SlaveSpec {
+                    count: Some(count),
+                    ..Default::default()
+                })
+                .collect(),
+        };
+        let info = mock_info("deployer", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let ids: Vec<u64> = res.messages.iter().map(|msg| msg.id).collect();
+
+        // the slave learns the master address from its instantiate message
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+                let msg: SlaveInstantiateMsg = from_json(msg).unwrap();
+                assert_eq!(MOCK_CONTRACT_ADDR, msg.master);
+            }
+            msg => panic!("Unexpected message: {:?}", msg),
+        }
+
+        let status = |deps: Deps, address: &str| {
+            SLAVES
+                .load(deps.storage, &Addr::unchecked(address))
+                .unwrap()
+                .status
+        };
+
+        // registered after the reply
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            instantiate_reply(ids[0], "slave0"),
+        )
+        .unwrap();
+        assert_eq!(SlaveStatus::Pending, status(deps.as_ref(), "slave0"));
+        let msg = ExecuteMsg::ExecuteOnSlave {
+            slave: "slave0".to_string(),
+            msg: Binary::from(b"{}"),
+            funds: None,
+            return_data: None,
+        };
+        let info = mock_info("deployer", &[]);
+        match execute(deps.as_mut(), mock_env(), info, msg) {
+            Err(ContractError::SlaveNotRegistered { .. }) => {}
+            _ => panic!("Must return slave not registered error"),
+        }
+        register_slave(&mut deps, "slave0");
+        assert_eq!(SlaveStatus::Active, status(deps.as_ref(), "slave0"));
+
+        // registered during instantiation, before the reply
+        register_slave(&mut deps, "slave1");
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            instantiate_reply(ids[1], "slave1"),
+        )
+        .unwrap();
+        assert_eq!(SlaveStatus::Active, status(deps.as_ref(), "slave1"));
+        assert!(EARLY_REGISTRATIONS.is_empty(&deps.storage));
+
+        // contracts of other creators or code ids are turned away
+        for (code_id, creator) in [
+            (SLAVE_CODE_ID + 1, MOCK_CONTRACT_ADDR),
+            (SLAVE_CODE_ID, "eve"),
+        ] {
+            deps.querier.update_wasm(move |_| {
+                let mut info = ContractInfoResponse::default();
+                info.code_id = code_id;
+                info.creator = creator.to_string();
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&info).unwrap()))
+            });
+            let info = mock_info("impostor", &[]);
+            match execute(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                ExecuteMsg::RegisterSlave {},
+            ) {
+                Err(ContractError::UnapprovedSlave {}) => {}
+                _ => panic!("Must return unapproved slave error"),
+            }
+        }
+    }
+
+    #[test]
+    fn count_history() {
+        let mut deps = mock_deps();
+
+        let at_height = |height: u64| {
+            let mut env = mock_env();
+            env.block.height = height;
+            env
+        };
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), at_height(100), info, msg).unwrap();
+
+        let info = mock_info("alice", &[]);
+        execute(
+            deps.as_mut(),
+            at_height(105),
+            info,
+            ExecuteMsg::Increment {},
+        )
+        .unwrap();
+        let info = mock_info("bob", &[]);
+        let msg = ExecuteMsg::Add { amount: 10 };
+        execute(deps.as_mut(), at_height(105), info, msg).unwrap();
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::Set { value: 3 };
+        execute(deps.as_mut(), at_height(110), info, msg).unwrap();
+
+        let count_at = |height| {
+            let msg = QueryMsg::GetCountAtHeight { height };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let value: CountAtHeightResponse = from_json(&res).unwrap();
+            value.count
+        };
+        assert_eq!(None, count_at(99));
+        assert_eq!(Some(17), count_at(100));
+        assert_eq!(Some(17), count_at(104));
+        // the count at the end of the block
+        assert_eq!(Some(28), count_at(105));
+        assert_eq!(Some(28), count_at(109));
+        assert_eq!(Some(3), count_at(110));
+        assert_eq!(Some(3), count_at(1000));
+
+        let history = |start_after, limit| {
+            let msg = QueryMsg::CountHistory { start_after, limit };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let value: CountHistoryResponse = from_json(&res).unwrap();
+            value.changes
+        };
+        let changes = history(None, None);
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|change| (change.height, change.actor.as_str(), change.old, change.new))
+            .collect();
+        assert_eq!(
+            vec![
+                (100, "creator", 0, 17),
+                (105, "alice", 17, 18),
+                (105, "bob", 18, 28),
+                (110, "creator", 28, 3)
+            ],
+            summary
+        );
+
+        let page = history(Some(changes[0].id), Some(1));
+        assert_eq!(vec![changes[1].clone()], page);
+    }
+
+    #[test]
+    fn user_counts_sum_up_to_count() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let ops = vec![
+            ("alice", ExecuteMsg::Increment {}),
+            ("alice", ExecuteMsg::Increment {}),
+            ("bob", ExecuteMsg::Add { amount: 10 }),
+            ("carol", ExecuteMsg::Decrement {}),
+            ("bob", ExecuteMsg::Add { amount: -4 }),
+            ("creator", ExecuteMsg::Set { value: 3 }),
+            ("alice", ExecuteMsg::Increment {}),
+        ];
+        for (sender, msg) in ops {
+            let info = mock_info(sender, &[]);
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+            let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+            let value: CountResponse = from_json(&res).unwrap();
+            let msg = QueryMsg::UserCounts {
+                start_after: None,
+                limit: None,
+            };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let users: UserCountsResponse = from_json(&res).unwrap();
+            let total: i64 = users.counts.iter().map(|user| user.count).sum();
+            assert_eq!(i64::from(value.count), total);
+        }
+
+        let user_count = |address: &str| {
+            let msg = QueryMsg::GetUserCount {
+                address: address.to_string(),
+            };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let value: UserCountResponse = from_json(&res).unwrap();
+            value.count
+        };
+        assert_eq!(3, user_count("alice"));
+        assert_eq!(6, user_count("bob"));
+        assert_eq!(-1, user_count("carol"));
+        // the owner carries the difference of the set
+        assert_eq!(17 + (3 - 24), user_count("creator"));
+        assert_eq!(0, user_count("dave"));
+
+        let msg = QueryMsg::UserCounts {
+            start_after: Some("bob".to_string()),
+            limit: Some(1),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let page: UserCountsResponse = from_json(&res).unwrap();
+        assert_eq!(
+            vec![UserCountResponse {
+                address: Addr::unchecked("carol"),
+                count: -1
+            }],
+            page.counts
+        );
+    }
+
+    #[test]
+    fn named_counters() {
+        let mut deps = mock_deps();
+
+        let msg = default_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let create = |name: &str, owner: Option<&str>, max| ExecuteMsg::CreateCounter {
+            name: name.to_string(),
+            initial: 5,
+            owner: owner.map(str::to_string),
+            bounds: CountBounds { min: None, max },
+        };
+        // only the contract owner creates counters
+        let info = mock_info("anyone", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            create("visits", None, Some(6)),
+        );
+        assert!(matches!(res, Err(ContractError::Unauthorized {})));
+
+        let info = mock_info("creator", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            create("visits", Some("anyone"), Some(6)),
+        )
+        .unwrap();
+        let info = mock_info("creator", &[]);
+        let msg = create("clicks", Some("alice"), None);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // names are unique and validated
+        let info = mock_info("creator", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            create("visits", None, None),
+        );
+        assert!(matches!(res, Err(ContractError::CounterExists { .. })));
+        let info = mock_info("creator", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            create("bad name", None, None),
+        );
+        assert!(matches!(res, Err(ContractError::InvalidCounterName { .. })));
+        let info = mock_info("creator", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            create("low", None, Some(4)),
+        );
+        assert!(matches!(
+            res,
+            Err(ContractError::CounterOutOfBounds { count: 5 })
+        ));
+
+        let increment = |name: &str| ExecuteMsg::IncrementNamed {
+            name: name.to_string(),
+        };
+        let info = mock_info("bob", &[]);
+        execute(deps.as_mut(), mock_env(), info, increment("visits")).unwrap();
+        let info = mock_info("bob", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, increment("visits"));
+        assert!(matches!(
+            res,
+            Err(ContractError::CounterOutOfBounds { count: 7 })
+        ));
+        let info = mock_info("bob", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, increment("missing"));
+        assert!(matches!(res, Err(ContractError::UnknownCounter { .. })));
+
+        // only the counter owner may reset it
+        let reset = ExecuteMsg::ResetNamed {
+            name: "clicks".to_string(),
+            count: 0,
+        };
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, reset.clone());
+        assert!(matches!(res, Err(ContractError::Unauthorized {})));
+        let info = mock_info("alice", &[]);
+        execute(deps.as_mut(), mock_env(), info, reset).unwrap();
+
+        let msg = ExecuteMsg::UpdateNamedCounterBounds {
+            name: "visits".to_string(),
+            bounds: CountBounds::default(),
+        };
+        let info = mock_info("anyone", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("bob", &[]);
+        execute(deps.as_mut(), mock_env(), info, increment("visits")).unwrap();
+
+        let msg = QueryMsg::NamedCounters {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: NamedCountersResponse = from_json(&res).unwrap();
+        let summary: Vec<_> = value
+            .counters
+            .iter()
+            .map(|counter| (counter.name.as_str(), counter.owner.as_str(), counter.count))
+            .collect();
+        assert_eq!(
+            vec![("clicks", "alice", 0), ("visits", "anyone", 7)],
+            summary
+        );
+
+        let msg = QueryMsg::NamedCounter {
+            name: "visits".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: NamedCounterResponse = from_json(&res).unwrap();
+        assert_eq!(7, value.count);
+
+        // the default counter is untouched
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let value: CountResponse = from_json(&res).unwrap();
+        assert_eq!(17, value.count);
     }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{CoinsError, OverflowError, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,11 +6,112 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Coins(#[from] CoinsError),
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Counter overflow")]
+    CounterOverflow {},
+
+    #[error("Count {count} is outside the configured bounds")]
+    CounterOutOfBounds { count: i32 },
+
+    #[error("Minimum count must not be above the maximum")]
+    InvalidCountBounds {},
+
+    #[error("Unknown counter: {name}")]
+    UnknownCounter { name: String },
+
+    #[error("Counter already exists: {name}")]
+    CounterExists { name: String },
+
+    #[error("Counter name must be 1 to {max} characters of letters, digits, '-', '_' or '.'")]
+    InvalidCounterName { max: usize },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
+
+    #[error("Unknown slave template: {name}")]
+    UnknownTemplate { name: String },
+
+    #[error("Slave template is disabled: {name}")]
+    TemplateDisabled { name: String },
+
+    #[error("Salt must be between 1 and 64 bytes long")]
+    InvalidSalt {},
+
+    #[error("Denom is not allowed to be forwarded to slaves: {denom}")]
+    UnsupportedDenom { denom: String },
+
+    #[error("Cannot forward more {denom} than attached")]
+    InsufficientFunds { denom: String },
+
+    #[error("Attached funds do not cover the deployment fee of {fee}")]
+    InsufficientFee { fee: String },
+
+    #[error("Cannot withdraw more {denom} fees than collected")]
+    InsufficientTreasury { denom: String },
+
+    #[error("Cannot withdraw nothing")]
+    EmptyWithdrawal {},
+
+    #[error("Cannot deploy an empty batch of slaves")]
+    EmptyBatch {},
+
+    #[error("Cannot deploy more than {max} slaves at once")]
+    BatchTooLarge { max: u32 },
+
+    #[error("Label must be 1 to {max} characters of letters, digits, spaces, '-', '_' or '.'")]
+    InvalidLabel { max: usize },
+
+    #[error(
+        "Label prefix must be 1 to {max} characters of letters, digits, spaces, '-', '_' or '.'"
+    )]
+    InvalidLabelPrefix { max: usize },
+
+    #[error("Template name must not be empty")]
+    InvalidTemplateName {},
+
+    #[error("No count given and no default instantiate message available")]
+    MissingInstantiateMsg {},
+
+    #[error("Default instantiate message must be a JSON object naming this contract as master")]
+    InvalidDefaultMsg {},
+
+    #[error("{address} is not a slave of this master")]
+    UnknownSlave { address: String },
+
+    #[error("No transfer of this slave is waiting for the sender")]
+    NoPendingTransfer {},
+
+    #[error("Slave {address} is archived")]
+    SlaveArchived { address: String },
+
+    #[error("Slave {address} did not register itself yet")]
+    SlaveNotRegistered { address: String },
+
+    #[error("Only slaves instantiated by this master from an approved code id can register")]
+    UnapprovedSlave {},
+
+    #[error("Code {code_id} does not have the expected checksum")]
+    ChecksumMismatch { code_id: u64 },
+
+    #[error("The master is not the wasm admin of this slave")]
+    NotSlaveAdmin {},
+
+    #[error("Creators may not have more than {max} live slaves")]
+    CreatorQuotaExceeded { max: u32 },
+
+    #[error("Creators may not deploy more than {max} slaves within {blocks} blocks")]
+    RateLimited { max: u32, blocks: u64 },
+
+    #[error("The master may not have more than {max} live slaves")]
+    GlobalQuotaExceeded { max: u64 },
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -2,20 +2,125 @@
 mod tests {
     use crate::helpers::CwTemplateContract;
     use crate::msg::InstantiateMsg;
+    use crate::state::{CountBounds, FailurePolicy, Quotas};
     use cosmwasm_std::{Addr, Coin, Empty, Uint128};
-    use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+    use cw_multi_test::{App, AppBuilder, AppResponse, Contract, ContractWrapper, Executor};
 
     pub fn contract_template() -> Box<dyn Contract<Empty>> {
         let contract = ContractWrapper::new(
             crate::contract::execute,
             crate::contract::instantiate,
             crate::contract::query,
-        );
+        )
+        .with_reply(crate::contract::reply);
         Box::new(contract)
     }
 
-    const USER: &str = "USER";
-    const ADMIN: &str = "ADMIN";
+    /// Minimal stand-in for the slave contract: it registers with the master,
+    /// keeps a count it can increment and reset, answers `GetCount` like the
+    /// master does and echoes data on execute.
+    mod slave {
+        use crate::msg::{
+            CountResponse, ExecuteMsg as MasterExecuteMsg, QueryMsg, SlaveInstantiateMsg,
+        };
+        use cosmwasm_std::{
+            to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+            WasmMsg,
+        };
+        use cw_storage_plus::Item;
+        use schemars::JsonSchema;
+        use serde::{Deserialize, Serialize};
+
+        const COUNT: Item<i32> = Item::new("count");
+
+        /// Resets the count, negative counts make the migration fail
+        #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+        pub struct MigrateMsg {
+            pub count: i32,
+        }
+
+        pub fn instantiate(
+            deps: DepsMut,
+            _env: Env,
+            _info: MessageInfo,
+            msg: SlaveInstantiateMsg,
+        ) -> StdResult<Response> {
+            // lets tests stand up a slave that fails to instantiate
+            if msg.count == i32::MAX {
+                return Err(StdError::generic_err("count out of range"));
+            }
+            COUNT.save(deps.storage, &msg.count)?;
+            // confirm ourselves with the master that deployed us
+            Ok(Response::new().add_message(WasmMsg::Execute {
+                contract_addr: msg.master,
+                msg: to_json_binary(&MasterExecuteMsg::RegisterSlave {})?,
+                funds: vec![],
+            }))
+        }
+
+        #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+        #[serde(rename_all = "snake_case")]
+        pub enum ExecuteMsg {
+            /// Sets `data` as the response data
+            Echo {
+                data: Binary,
+            },
+            Increment {},
+            Reset {
+                count: i32,
+            },
+        }
+
+        pub fn execute(
+            deps: DepsMut,
+            _env: Env,
+            _info: MessageInfo,
+            msg: ExecuteMsg,
+        ) -> StdResult<Response> {
+            match msg {
+                ExecuteMsg::Echo { data } => Ok(Response::new().set_data(data)),
+                ExecuteMsg::Increment {} => {
+                    COUNT.update(deps.storage, |count| -> StdResult<_> { Ok(count + 1) })?;
+                    Ok(Response::new())
+                }
+                ExecuteMsg::Reset { count } => {
+                    COUNT.save(deps.storage, &count)?;
+                    Ok(Response::new())
+                }
+            }
+        }
+
+        pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response> {
+            if msg.count < 0 {
+                return Err(StdError::generic_err("negative count"));
+            }
+            COUNT.save(deps.storage, &msg.count)?;
+            Ok(Response::new())
+        }
+
+        pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+            match msg {
+                QueryMsg::GetCount {} => {
+                    let count = COUNT.load(deps.storage)?;
+                    // lets tests stand up a slave that cannot be queried
+                    if count < 0 {
+                        return Err(StdError::generic_err("broken slave"));
+                    }
+                    to_json_binary(&CountResponse { count })
+                }
+                _ => Err(StdError::generic_err("not implemented")),
+            }
+        }
+    }
+
+    pub fn contract_slave() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(slave::execute, slave::instantiate, slave::query)
+            .with_migrate(slave::migrate);
+        Box::new(contract)
+    }
+
+    const USER: &str = "user";
+    const ADMIN: &str = "admin";
     const NATIVE_DENOM: &str = "denom";
 
     fn mock_app() -> App {
@@ -27,7 +132,7 @@ mod tests {
                     &Addr::unchecked(USER),
                     vec![Coin {
                         denom: NATIVE_DENOM.to_string(),
-                        amount: Uint128::new(1),
+                        amount: Uint128::new(1000),
                     }],
                 )
                 .unwrap();
@@ -37,8 +142,24 @@ mod tests {
     fn proper_instantiate() -> (App, CwTemplateContract) {
         let mut app = mock_app();
         let cw_template_id = app.store_code(contract_template());
+        let slave_code_id = app.store_code(contract_slave());
+        let slave_checksum = app
+            .wrap()
+            .query_wasm_code_info(slave_code_id)
+            .unwrap()
+            .checksum;
 
-        let msg = InstantiateMsg { count: 1i32 };
+        let msg = InstantiateMsg {
+            count: 1i32,
+            slave_code_id,
+            slave_checksum,
+            allowed_denoms: vec![NATIVE_DENOM.to_string()],
+            deploy_fee: vec![],
+            max_batch_size: 5,
+            quotas: Quotas::default(),
+            failure_policy: FailurePolicy::Revert,
+            count_bounds: CountBounds::default(),
+        };
         let cw_template_contract_addr = app
             .instantiate_contract(
                 cw_template_id,
@@ -68,4 +189,1012 @@ mod tests {
             app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
         }
     }
+
+    mod deploy {
+        use super::*;
+        use crate::msg::{
+            AdminPolicy, ConfigResponse, CountResponse, DeploySlavesResponse, ExecuteMsg,
+            ForwardFunds, MigrateSlavesResponse, PredictSlaveAddressResponse, QueryMsg,
+            SlaveInstantiateMsg, SlaveSalt, SlaveSpec, SlavesResponse,
+        };
+        use crate::state::SlaveStatus;
+        use crate::ContractError;
+        use cosmwasm_std::{coins, from_json, to_json_binary, Binary, HexBinary};
+        use cw_multi_test::addons::{MockAddressGenerator, MockApiBech32};
+        use cw_multi_test::WasmKeeper;
+
+        #[test]
+        fn deploy_slave() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let msg = ExecuteMsg::from(SlaveSpec {
+                count: Some(7),
+                ..Default::default()
+            });
+            let cosmos_msg = cw_template_contract.call(msg).unwrap();
+            app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+            let res: SlavesResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::ListSlaves {
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(1, res.slaves.len());
+            let slave = &res.slaves[0];
+            assert_eq!(Addr::unchecked(USER), slave.creator);
+            assert_eq!(Some(7), slave.initial_count);
+
+            // the registered address is the freshly instantiated slave
+            let count: CountResponse = app
+                .wrap()
+                .query_wasm_smart(slave.address.clone(), &QueryMsg::GetCount {})
+                .unwrap();
+            assert_eq!(7, count.count);
+        }
+
+        #[test]
+        fn deploy_slaves_in_batch() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let spec = |count| SlaveSpec {
+                count: Some(count),
+                ..Default::default()
+            };
+            let msg = ExecuteMsg::DeploySlaves {
+                slaves: vec![spec(1), spec(2), spec(3)],
+            };
+            let res = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    cw_template_contract.addr(),
+                    &msg,
+                    &[],
+                )
+                .unwrap();
+            let data: DeploySlavesResponse = from_json(res.data.unwrap()).unwrap();
+            assert_eq!(3, data.slaves.len());
+
+            for (slave, expected) in data.slaves.iter().zip(1..) {
+                let count: CountResponse = app
+                    .wrap()
+                    .query_wasm_smart(slave, &QueryMsg::GetCount {})
+                    .unwrap();
+                assert_eq!(expected, count.count);
+            }
+
+            let res: SlavesResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::ListSlaves {
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(3, res.slaves.len());
+
+            let msg = ExecuteMsg::DeploySlaves {
+                slaves: (0..6).map(spec).collect(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    cw_template_contract.addr(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::BatchTooLarge { max: 5 }
+            ));
+        }
+
+        #[test]
+        fn deploy_slave_from_template_default_msg() {
+            let (mut app, master) = proper_instantiate();
+
+            let config: ConfigResponse = app
+                .wrap()
+                .query_wasm_smart(master.addr(), &QueryMsg::Config {})
+                .unwrap();
+            let msg = ExecuteMsg::SetTemplate {
+                name: "preset".to_string(),
+                code_id: config.slave_code_id,
+                checksum: config.slave_checksum,
+                label_prefix: "Preset".to_string(),
+                default_msg: Some(
+                    to_json_binary(&SlaveInstantiateMsg {
+                        count: 42,
+                        master: master.addr().to_string(),
+                    })
+                    .unwrap(),
+                ),
+                enabled: true,
+            };
+            app.execute(Addr::unchecked(ADMIN), master.call(msg).unwrap())
+                .unwrap();
+
+            let msg = ExecuteMsg::from(SlaveSpec {
+                template: Some("preset".to_string()),
+                ..Default::default()
+            });
+            app.execute(Addr::unchecked(USER), master.call(msg).unwrap())
+                .unwrap();
+
+            // the slave found its way back to the master and registered
+            let res: SlavesResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    master.addr(),
+                    &QueryMsg::ListSlaves {
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(1, res.slaves.len());
+            let slave = &res.slaves[0];
+            assert_eq!(SlaveStatus::Active, slave.status);
+            assert_eq!(Some("preset".to_string()), slave.template);
+            let count: CountResponse = app
+                .wrap()
+                .query_wasm_smart(&slave.address, &QueryMsg::GetCount {})
+                .unwrap();
+            assert_eq!(42, count.count);
+        }
+
+        #[test]
+        fn deploy_slave_with_funds() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let msg = ExecuteMsg::from(SlaveSpec {
+                count: Some(7),
+                forward_funds: Some(ForwardFunds::All {}),
+                ..Default::default()
+            });
+            let funds = coins(1, NATIVE_DENOM);
+            app.execute_contract(
+                Addr::unchecked(USER),
+                cw_template_contract.addr(),
+                &msg,
+                &funds,
+            )
+            .unwrap();
+
+            let res: SlavesResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::ListSlaves {
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            let balance = app
+                .wrap()
+                .query_all_balances(&res.slaves[0].address)
+                .unwrap();
+            assert_eq!(funds, balance);
+            let balance = app
+                .wrap()
+                .query_all_balances(cw_template_contract.addr())
+                .unwrap();
+            assert!(balance.is_empty());
+        }
+
+        #[test]
+        fn deploy_slave_at_predicted_address() {
+            // Instantiate2 addresses are only derived like on chain with a bech32 api
+            let mut app = AppBuilder::default()
+                .with_api(MockApiBech32::new("juno"))
+                .with_wasm(WasmKeeper::default().with_address_generator(MockAddressGenerator))
+                .build(|_, _, _| {});
+            let user = app.api().addr_make(USER);
+            let cw_template_id = app.store_code(contract_template());
+            let slave_code_id = app.store_code(contract_slave());
+            let slave_checksum = app
+                .wrap()
+                .query_wasm_code_info(slave_code_id)
+                .unwrap()
+                .checksum;
+            let master = app
+                .instantiate_contract(
+                    cw_template_id,
+                    app.api().addr_make(ADMIN),
+                    &InstantiateMsg {
+                        count: 1i32,
+                        slave_code_id,
+                        slave_checksum,
+                        allowed_denoms: vec![],
+                        deploy_fee: vec![],
+                        max_batch_size: 5,
+                        quotas: Quotas::default(),
+                        failure_policy: FailurePolicy::Revert,
+                        count_bounds: CountBounds::default(),
+                    },
+                    &[],
+                    "test",
+                    None,
+                )
+                .unwrap();
+            let master = CwTemplateContract(master);
+
+            let predict = |app: &App<_, _>, creator: &Addr, salt: Option<Binary>| {
+                let res: PredictSlaveAddressResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        master.addr(),
+                        &QueryMsg::PredictSlaveAddress {
+                            template: None,
+                            salt,
+                            creator: creator.to_string(),
+                        },
+                    )
+                    .unwrap();
+                res.address
+            };
+            let custom = predict(&app, &user, Some(Binary::from(b"custom")));
+            let derived = predict(&app, &user, None);
+
+            for salt in [
+                SlaveSalt::Custom {
+                    salt: Binary::from(b"custom"),
+                },
+                SlaveSalt::Derived {},
+            ] {
+                let msg = ExecuteMsg::from(SlaveSpec {
+                    count: Some(3),
+                    salt: Some(salt),
+                    ..Default::default()
+                });
+                app.execute(user.clone(), master.call(msg).unwrap())
+                    .unwrap();
+            }
+
+            for address in [&custom, &derived] {
+                let count: CountResponse = app
+                    .wrap()
+                    .query_wasm_smart(address, &QueryMsg::GetCount {})
+                    .unwrap();
+                assert_eq!(3, count.count);
+            }
+
+            // the derived salt moved on with the deployment
+            assert_ne!(derived, predict(&app, &user, None));
+
+            // another deployer reusing the custom salt gets an address of their own
+            let other = app.api().addr_make("other");
+            let other_custom = predict(&app, &other, Some(Binary::from(b"custom")));
+            assert_ne!(custom, other_custom);
+            let msg = ExecuteMsg::from(SlaveSpec {
+                count: Some(4),
+                salt: Some(SlaveSalt::Custom {
+                    salt: Binary::from(b"custom"),
+                }),
+                ..Default::default()
+            });
+            app.execute(other, master.call(msg).unwrap()).unwrap();
+            let count: CountResponse = app
+                .wrap()
+                .query_wasm_smart(&other_custom, &QueryMsg::GetCount {})
+                .unwrap();
+            assert_eq!(4, count.count);
+        }
+
+        #[test]
+        fn deploy_slave_checks_code_checksum() {
+            let (mut app, master) = proper_instantiate();
+            let config: ConfigResponse = app
+                .wrap()
+                .query_wasm_smart(master.addr(), &QueryMsg::Config {})
+                .unwrap();
+            let code_id = config.slave_code_id;
+
+            let update = |checksum| ExecuteMsg::UpdateSlaveCodeId { code_id, checksum };
+            let deploy = ExecuteMsg::from(SlaveSpec {
+                count: Some(1),
+                ..Default::default()
+            });
+
+            // the checksum given on instantiation matches the stored code
+            app.execute_contract(Addr::unchecked(USER), master.addr(), &deploy, &[])
+                .unwrap();
+
+            let wrong = HexBinary::from(vec![0u8; 32]);
+            app.execute_contract(Addr::unchecked(ADMIN), master.addr(), &update(wrong), &[])
+                .unwrap();
+            let err = app
+                .execute_contract(Addr::unchecked(USER), master.addr(), &deploy, &[])
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::ChecksumMismatch { code_id: id } if id == code_id
+            ));
+
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                master.addr(),
+                &update(config.slave_checksum),
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(Addr::unchecked(USER), master.addr(), &deploy, &[])
+                .unwrap();
+        }
+
+        #[test]
+        fn migrate_slaves() {
+            let (mut app, master) = proper_instantiate();
+            let new_code_id = app.store_code(contract_slave());
+            let checksum = app
+                .wrap()
+                .query_wasm_code_info(new_code_id)
+                .unwrap()
+                .checksum;
+
+            for admin in [AdminPolicy::Master {}, AdminPolicy::Sender {}] {
+                let msg = ExecuteMsg::from(SlaveSpec {
+                    count: Some(1),
+                    admin: Some(admin),
+                    ..Default::default()
+                });
+                app.execute_contract(Addr::unchecked(USER), master.addr(), &msg, &[])
+                    .unwrap();
+            }
+            let slaves = |app: &App| {
+                let res: SlavesResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        master.addr(),
+                        &QueryMsg::ListSlaves {
+                            start_after: None,
+                            limit: None,
+                        },
+                    )
+                    .unwrap();
+                res.slaves
+            };
+            let migrate = |count, start_after, limit| ExecuteMsg::MigrateSlaves {
+                new_code_id,
+                checksum: checksum.clone(),
+                msg: to_json_binary(&slave::MigrateMsg { count }).unwrap(),
+                start_after,
+                limit,
+            };
+
+            // only the owner may migrate
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    master.addr(),
+                    &migrate(42, None, None),
+                    &[],
+                )
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            ));
+
+            // the new code has to match the checksum
+            let msg = ExecuteMsg::MigrateSlaves {
+                new_code_id,
+                checksum: HexBinary::from(vec![0u8; 32]),
+                msg: to_json_binary(&slave::MigrateMsg { count: 42 }).unwrap(),
+                start_after: None,
+                limit: None,
+            };
+            let err = app
+                .execute_contract(Addr::unchecked(ADMIN), master.addr(), &msg, &[])
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::ChecksumMismatch { code_id } if code_id == new_code_id
+            ));
+
+            // the fleet is processed page by page
+            let res = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    master.addr(),
+                    &migrate(42, None, Some(1)),
+                    &[],
+                )
+                .unwrap();
+            let page: MigrateSlavesResponse = from_json(res.data.unwrap()).unwrap();
+            assert_eq!(1, page.migrating + page.skipped);
+            let first = page.migrating;
+            let start_after = page.last.map(Addr::into_string);
+            assert!(start_after.is_some());
+            let res = app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    master.addr(),
+                    &migrate(42, start_after, None),
+                    &[],
+                )
+                .unwrap();
+            let page: MigrateSlavesResponse = from_json(res.data.unwrap()).unwrap();
+            assert_eq!(1, first + page.migrating);
+            assert_eq!(None, page.last);
+            for slave in slaves(&app) {
+                let count: CountResponse = app
+                    .wrap()
+                    .query_wasm_smart(&slave.address, &QueryMsg::GetCount {})
+                    .unwrap();
+                if slave.admin == Some(Addr::unchecked(USER)) {
+                    // administered by the deployer, left alone
+                    assert_eq!(1, count.count);
+                    assert_eq!(None, slave.last_migration);
+                } else {
+                    assert_eq!(42, count.count);
+                    assert_eq!(new_code_id, slave.code_id);
+                    let migration = slave.last_migration.unwrap();
+                    assert_eq!(new_code_id, migration.code_id);
+                    assert_eq!(None, migration.error);
+                }
+            }
+
+            // a failing migration is recorded without reverting the run
+            let old_code_id = new_code_id;
+            let new_code_id = app.store_code(contract_slave());
+            let msg = ExecuteMsg::MigrateSlaves {
+                new_code_id,
+                checksum: app
+                    .wrap()
+                    .query_wasm_code_info(new_code_id)
+                    .unwrap()
+                    .checksum,
+                msg: to_json_binary(&slave::MigrateMsg { count: -1 }).unwrap(),
+                start_after: None,
+                limit: None,
+            };
+            app.execute_contract(Addr::unchecked(ADMIN), master.addr(), &msg, &[])
+                .unwrap();
+            let migrated = slaves(&app)
+                .into_iter()
+                .find(|slave| slave.last_migration.is_some())
+                .unwrap();
+            assert_eq!(old_code_id, migrated.code_id);
+            let migration = migrated.last_migration.unwrap();
+            assert_eq!(new_code_id, migration.code_id);
+            assert!(migration.error.is_some());
+        }
+    }
+
+    mod aggregate {
+        use super::*;
+        use crate::msg::{AggregateSlaveCountsResponse, ExecuteMsg, QueryMsg, SlaveSpec};
+
+        #[test]
+        fn aggregate_slave_counts() {
+            let (mut app, master) = proper_instantiate();
+
+            let spec = |count| SlaveSpec {
+                count: Some(count),
+                ..Default::default()
+            };
+            let msg = ExecuteMsg::DeploySlaves {
+                slaves: vec![spec(1), spec(-1), spec(3)],
+            };
+            app.execute_contract(Addr::unchecked(USER), master.addr(), &msg, &[])
+                .unwrap();
+
+            let aggregate = |start_after, limit| {
+                let res: AggregateSlaveCountsResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        master.addr(),
+                        &QueryMsg::AggregateSlaveCounts { start_after, limit },
+                    )
+                    .unwrap();
+                res
+            };
+
+            let all = aggregate(None, None);
+            assert_eq!(3, all.counts.len());
+            assert_eq!(4, all.total);
+            assert_eq!(None, all.last);
+            let broken: Vec<_> = all.counts.iter().filter(|c| c.error.is_some()).collect();
+            assert_eq!(1, broken.len());
+            assert_eq!(None, broken[0].count);
+
+            // pages add up to the same total
+            let first = aggregate(None, Some(2));
+            assert_eq!(2, first.counts.len());
+            let last = first.last.unwrap();
+            let second = aggregate(Some(last.to_string()), Some(2));
+            assert_eq!(1, second.counts.len());
+            assert_eq!(None, second.last);
+            assert_eq!(all.total, first.total + second.total);
+        }
+    }
+
+    mod broadcast {
+        use super::*;
+        use crate::msg::{
+            BroadcastToSlavesResponse, CountResponse, DeploySlavesResponse, ExecuteMsg, QueryMsg,
+            SlaveExecuteMsg, SlaveSpec,
+        };
+        use crate::ContractError;
+        use cosmwasm_std::from_json;
+
+        #[test]
+        fn broadcast_to_slaves() {
+            let (mut app, master) = proper_instantiate();
+
+            let spec = |count| SlaveSpec {
+                count: Some(count),
+                ..Default::default()
+            };
+            let msg = ExecuteMsg::DeploySlaves {
+                slaves: vec![spec(1), spec(2), spec(3)],
+            };
+            let res = app
+                .execute_contract(Addr::unchecked(USER), master.addr(), &msg, &[])
+                .unwrap();
+            let data: DeploySlavesResponse = from_json(res.data.unwrap()).unwrap();
+            let counts = |app: &App| {
+                let mut counts: Vec<i32> = data
+                    .slaves
+                    .iter()
+                    .map(|slave| {
+                        let res: CountResponse = app
+                            .wrap()
+                            .query_wasm_smart(slave, &QueryMsg::GetCount {})
+                            .unwrap();
+                        res.count
+                    })
+                    .collect();
+                counts.sort_unstable();
+                counts
+            };
+            let broadcast = |action, start_after| ExecuteMsg::BroadcastToSlaves {
+                action,
+                start_after,
+                limit: Some(2),
+            };
+
+            // only the owner may broadcast
+            let msg = broadcast(SlaveExecuteMsg::Increment {}, None);
+            let err = app
+                .execute_contract(Addr::unchecked(USER), master.addr(), &msg, &[])
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            ));
+
+            // the fleet is processed page by page
+            let res = app
+                .execute_contract(Addr::unchecked(ADMIN), master.addr(), &msg, &[])
+                .unwrap();
+            let page: BroadcastToSlavesResponse = from_json(res.data.unwrap()).unwrap();
+            assert_eq!(2, page.targeted);
+            let msg = broadcast(
+                SlaveExecuteMsg::Increment {},
+                page.last.map(Addr::into_string),
+            );
+            let res = app
+                .execute_contract(Addr::unchecked(ADMIN), master.addr(), &msg, &[])
+                .unwrap();
+            let page: BroadcastToSlavesResponse = from_json(res.data.unwrap()).unwrap();
+            assert_eq!(1, page.targeted);
+            assert_eq!(None, page.last);
+            assert_eq!(vec![2, 3, 4], counts(&app));
+
+            let msg = ExecuteMsg::BroadcastToSlaves {
+                action: SlaveExecuteMsg::Reset { count: 0 },
+                start_after: None,
+                limit: None,
+            };
+            app.execute_contract(Addr::unchecked(ADMIN), master.addr(), &msg, &[])
+                .unwrap();
+            assert_eq!(vec![0, 0, 0], counts(&app));
+        }
+    }
+
+    mod relay {
+        use super::*;
+        use crate::msg::{DeploySlavesResponse, ExecuteMsg, ForwardFunds, SlaveSpec};
+        use crate::ContractError;
+        use cosmwasm_std::{coins, from_json, to_json_binary, Binary};
+
+        fn deploy_slave(app: &mut App, master: &CwTemplateContract) -> Addr {
+            let msg = ExecuteMsg::DeploySlaves {
+                slaves: vec![SlaveSpec {
+                    count: Some(1),
+                    ..Default::default()
+                }],
+            };
+            let res = app
+                .execute_contract(Addr::unchecked(USER), master.addr(), &msg, &[])
+                .unwrap();
+            let data: DeploySlavesResponse = from_json(res.data.unwrap()).unwrap();
+            data.slaves[0].clone()
+        }
+
+        #[test]
+        fn execute_on_slave() {
+            let (mut app, master) = proper_instantiate();
+            let slave = deploy_slave(&mut app, &master);
+
+            let echo = to_json_binary(&slave::ExecuteMsg::Echo {
+                data: Binary::from(b"pong"),
+            })
+            .unwrap();
+            let msg = ExecuteMsg::ExecuteOnSlave {
+                slave: slave.to_string(),
+                msg: echo.clone(),
+                funds: Some(ForwardFunds::Coins {
+                    amount: coins(100, NATIVE_DENOM),
+                }),
+                return_data: Some(true),
+            };
+            let res = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    master.addr(),
+                    &msg,
+                    &coins(150, NATIVE_DENOM),
+                )
+                .unwrap();
+            assert_eq!(Some(Binary::from(b"pong")), res.data);
+
+            // the forwarded funds reach the slave, the rest is refunded
+            let balance = |address: &str| {
+                app.wrap()
+                    .query_balance(address, NATIVE_DENOM)
+                    .unwrap()
+                    .amount
+                    .u128()
+            };
+            assert_eq!(100, balance(slave.as_str()));
+            assert_eq!(900, balance(USER));
+            assert_eq!(0, balance(master.addr().as_str()));
+
+            // without return_data the slave's data is dropped
+            let msg = ExecuteMsg::ExecuteOnSlave {
+                slave: slave.to_string(),
+                msg: echo.clone(),
+                funds: None,
+                return_data: None,
+            };
+            let res = app
+                .execute_contract(Addr::unchecked(USER), master.addr(), &msg, &[])
+                .unwrap();
+            assert_eq!(None, res.data);
+
+            // only the owner may drive the slave
+            let err = app
+                .execute_contract(Addr::unchecked(ADMIN), master.addr(), &msg, &[])
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            ));
+
+            let msg = ExecuteMsg::ExecuteOnSlave {
+                slave: master.addr().to_string(),
+                msg: echo,
+                funds: None,
+                return_data: None,
+            };
+            let err = app
+                .execute_contract(Addr::unchecked(USER), master.addr(), &msg, &[])
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::UnknownSlave { .. }
+            ));
+        }
+    }
+
+    mod decommission {
+        use super::*;
+        use crate::msg::{
+            AdminPolicy, DecommissionMode, DeploySlavesResponse, ExecuteMsg, QueryMsg,
+            SlaveResponse, SlaveSpec,
+        };
+        use crate::state::SlaveStatus;
+        use crate::ContractError;
+        use cosmwasm_std::{from_json, Binary};
+
+        #[test]
+        fn decommission_slave() {
+            let (mut app, master) = proper_instantiate();
+
+            let spec = |admin| SlaveSpec {
+                count: Some(1),
+                admin: Some(admin),
+                ..Default::default()
+            };
+            let msg = ExecuteMsg::DeploySlaves {
+                slaves: vec![
+                    spec(AdminPolicy::Master {}),
+                    spec(AdminPolicy::Master {}),
+                    spec(AdminPolicy::Sender {}),
+                ],
+            };
+            let res = app
+                .execute_contract(Addr::unchecked(USER), master.addr(), &msg, &[])
+                .unwrap();
+            let data: DeploySlavesResponse = from_json(res.data.unwrap()).unwrap();
+            let slaves = data.slaves;
+
+            let decommission = |app: &mut App, sender: &str, slave: &Addr, mode| {
+                let msg = ExecuteMsg::DecommissionSlave {
+                    slave: slave.to_string(),
+                    mode,
+                };
+                app.execute_contract(Addr::unchecked(sender), master.addr(), &msg, &[])
+            };
+            let registered = |app: &App, slave: &Addr| {
+                let res: SlaveResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        master.addr(),
+                        &QueryMsg::Slave {
+                            address: slave.to_string(),
+                        },
+                    )
+                    .unwrap();
+                res
+            };
+            let wasm_admin =
+                |app: &App, slave: &Addr| app.wrap().query_wasm_contract_info(slave).unwrap().admin;
+
+            // strangers cannot decommission
+            let err = decommission(
+                &mut app,
+                "stranger",
+                &slaves[0],
+                DecommissionMode::Archive {},
+            )
+            .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            ));
+
+            decommission(&mut app, USER, &slaves[0], DecommissionMode::HandToOwner {}).unwrap();
+            assert_eq!(Some(USER.to_string()), wasm_admin(&app, &slaves[0]));
+            let slave = registered(&app, &slaves[0]);
+            assert_eq!(SlaveStatus::Archived, slave.status);
+            assert_eq!(Some(Addr::unchecked(USER)), slave.admin);
+
+            // the master owner may decommission any slave
+            decommission(&mut app, ADMIN, &slaves[1], DecommissionMode::ClearAdmin {}).unwrap();
+            assert_eq!(None, wasm_admin(&app, &slaves[1]));
+            assert_eq!(SlaveStatus::Archived, registered(&app, &slaves[1]).status);
+
+            // admin handover needs the master to be the admin
+            let err = decommission(&mut app, USER, &slaves[2], DecommissionMode::ClearAdmin {})
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::NotSlaveAdmin {}
+            ));
+            decommission(&mut app, USER, &slaves[2], DecommissionMode::Archive {}).unwrap();
+            assert_eq!(Some(USER.to_string()), wasm_admin(&app, &slaves[2]));
+            assert_eq!(SlaveStatus::Archived, registered(&app, &slaves[2]).status);
+
+            // archived slaves can no longer be driven through the master
+            let msg = ExecuteMsg::ExecuteOnSlave {
+                slave: slaves[2].to_string(),
+                msg: Binary::from(b"{}"),
+                funds: None,
+                return_data: None,
+            };
+            let err = app
+                .execute_contract(Addr::unchecked(USER), master.addr(), &msg, &[])
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::SlaveArchived { .. }
+            ));
+        }
+    }
+
+    mod fees {
+        use super::*;
+        use crate::msg::{
+            CollectedFeesResponse, DeploySlavesResponse, ExecuteMsg, FailedDeploymentsResponse,
+            ForwardFunds, QueryMsg, SlaveSpec,
+        };
+        use crate::ContractError;
+        use cosmwasm_std::{coins, from_json};
+        use cw_multi_test::error::AnyResult;
+
+        const FEE: u128 = 10;
+
+        fn instantiate_with_fee() -> (App, CwTemplateContract) {
+            let (mut app, cw_template_contract) = proper_instantiate();
+            let msg = ExecuteMsg::UpdateDeployFee {
+                fee: coins(FEE, NATIVE_DENOM),
+            };
+            let cosmos_msg = cw_template_contract.call(msg).unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+            (app, cw_template_contract)
+        }
+
+        fn try_deploy(
+            app: &mut App,
+            contract: &CwTemplateContract,
+            amount: u128,
+        ) -> AnyResult<AppResponse> {
+            let msg = ExecuteMsg::from(SlaveSpec {
+                count: Some(1),
+                ..Default::default()
+            });
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract.addr(),
+                &msg,
+                &coins(amount, NATIVE_DENOM),
+            )
+        }
+
+        fn deploy(app: &mut App, contract: &CwTemplateContract, amount: u128) -> AppResponse {
+            try_deploy(app, contract, amount).unwrap()
+        }
+
+        fn balance(app: &App, address: impl Into<String>) -> u128 {
+            app.wrap()
+                .query_balance(address, NATIVE_DENOM)
+                .unwrap()
+                .amount
+                .u128()
+        }
+
+        #[test]
+        fn underpayment() {
+            let (mut app, cw_template_contract) = instantiate_with_fee();
+
+            let err = try_deploy(&mut app, &cw_template_contract, FEE - 1).unwrap_err();
+            match err.downcast::<ContractError>().unwrap() {
+                ContractError::InsufficientFee { fee } => assert_eq!("10denom", fee),
+                err => panic!("Unexpected error: {}", err),
+            }
+            assert_eq!(1000, balance(&app, USER));
+        }
+
+        #[test]
+        fn overpayment_is_refunded() {
+            let (mut app, cw_template_contract) = instantiate_with_fee();
+
+            deploy(&mut app, &cw_template_contract, FEE + 5);
+
+            assert_eq!(1000 - FEE, balance(&app, USER));
+            assert_eq!(FEE, balance(&app, cw_template_contract.addr()));
+
+            let res: CollectedFeesResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::CollectedFees {})
+                .unwrap();
+            assert_eq!(1, res.fees.len());
+            assert_eq!(NATIVE_DENOM, res.fees[0].denom);
+            assert_eq!(FEE, res.fees[0].collected.u128());
+            assert_eq!(FEE, res.fees[0].available.u128());
+        }
+
+        #[test]
+        fn withdrawal() {
+            let (mut app, cw_template_contract) = instantiate_with_fee();
+            deploy(&mut app, &cw_template_contract, FEE);
+            deploy(&mut app, &cw_template_contract, FEE);
+
+            let withdraw = |amount| ExecuteMsg::WithdrawFees {
+                to: "treasury".to_string(),
+                amount: coins(amount, NATIVE_DENOM),
+            };
+
+            // only the owner can withdraw
+            let cosmos_msg = cw_template_contract.call(withdraw(FEE)).unwrap();
+            let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            ));
+
+            let cosmos_msg = cw_template_contract.call(withdraw(15)).unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+            assert_eq!(15, balance(&app, "treasury"));
+
+            // cannot take out more than what is left
+            let cosmos_msg = cw_template_contract.call(withdraw(6)).unwrap();
+            let err = app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::InsufficientTreasury { .. }
+            ));
+
+            // nor nothing at all
+            for amount in [vec![], coins(0, NATIVE_DENOM)] {
+                let msg = ExecuteMsg::WithdrawFees {
+                    to: "treasury".to_string(),
+                    amount,
+                };
+                let cosmos_msg = cw_template_contract.call(msg).unwrap();
+                let err = app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap_err();
+                assert!(matches!(
+                    err.downcast::<ContractError>().unwrap(),
+                    ContractError::EmptyWithdrawal {}
+                ));
+            }
+
+            let res: CollectedFeesResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::CollectedFees {})
+                .unwrap();
+            assert_eq!(2 * FEE, res.fees[0].collected.u128());
+            assert_eq!(15, res.fees[0].withdrawn.u128());
+            assert_eq!(5, res.fees[0].available.u128());
+        }
+
+        #[test]
+        fn failed_deployment_is_refunded() {
+            let (mut app, master) = instantiate_with_fee();
+
+            let spec = |count| SlaveSpec {
+                count: Some(count),
+                forward_funds: Some(ForwardFunds::Coins {
+                    amount: coins(5, NATIVE_DENOM),
+                }),
+                ..Default::default()
+            };
+            let msg = ExecuteMsg::DeploySlaves {
+                slaves: vec![spec(1), spec(i32::MAX), spec(2)],
+            };
+            let funds = coins(3 * (FEE + 5), NATIVE_DENOM);
+
+            // by default a failing slave reverts the whole batch
+            app.execute_contract(Addr::unchecked(USER), master.addr(), &msg, &funds)
+                .unwrap_err();
+            assert_eq!(1000, balance(&app, USER));
+
+            let policy = ExecuteMsg::UpdateFailurePolicy {
+                policy: FailurePolicy::Record,
+            };
+            app.execute_contract(Addr::unchecked(ADMIN), master.addr(), &policy, &[])
+                .unwrap();
+            let res = app
+                .execute_contract(Addr::unchecked(USER), master.addr(), &msg, &funds)
+                .unwrap();
+            let data: DeploySlavesResponse = from_json(res.data.unwrap()).unwrap();
+            assert_eq!(2, data.slaves.len());
+
+            // fee and funds of the failed slave went back to the user
+            assert_eq!(1000 - 2 * (FEE + 5), balance(&app, USER));
+            let res: CollectedFeesResponse = app
+                .wrap()
+                .query_wasm_smart(master.addr(), &QueryMsg::CollectedFees {})
+                .unwrap();
+            assert_eq!(2 * FEE, res.fees[0].collected.u128());
+
+            let res: FailedDeploymentsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    master.addr(),
+                    &QueryMsg::FailedDeployments {
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(1, res.failures.len());
+            let failure = &res.failures[0];
+            assert_eq!(Addr::unchecked(USER), failure.requester);
+            assert_eq!(coins(FEE + 5, NATIVE_DENOM), failure.refunded);
+            assert!(failure.error.contains("count out of range"));
+        }
+    }
 }
@@ -1,18 +1,230 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use cosmwasm_std::{Addr, Binary, Coin, HexBinary, Timestamp, Uint128};
+
+use crate::state::{CountBounds, FailurePolicy, MigrationRecord, Quotas, SlaveStatus};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub count: i32,
+    pub slave_code_id: u64,
+    /// Wasm checksum of `slave_code_id`, checked before every deployment
+    pub slave_checksum: HexBinary,
+    pub allowed_denoms: Vec<String>,
+    pub deploy_fee: Vec<Coin>,
+    pub max_batch_size: u32,
+    pub quotas: Quotas,
+    pub failure_policy: FailurePolicy,
+    pub count_bounds: CountBounds,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     Increment {},
-    DeploySlave {
+    Decrement {},
+    Add {
+        amount: i32,
+    },
+    /// Owner only
+    Set {
+        value: i32,
+    },
+    /// Owner only, same as `Set`
+    Reset {
+        count: i32,
+    },
+    /// Owner only, `owner` manages the new counter
+    CreateCounter {
+        name: String,
+        initial: i32,
+        /// Defaults to the sender
+        owner: Option<String>,
+        bounds: CountBounds,
+    },
+    IncrementNamed {
+        name: String,
+    },
+    /// Counter owner only
+    ResetNamed {
+        name: String,
         count: i32,
     },
+    /// Counter owner only
+    UpdateNamedCounterBounds {
+        name: String,
+        bounds: CountBounds,
+    },
+    DeploySlave {
+        /// Template to deploy, the configured slave code id is used when omitted
+        template: Option<String>,
+        /// Falls back to the template default message when omitted
+        count: Option<i32>,
+        /// Deploy with `Instantiate2` so the slave address is known upfront
+        salt: Option<SlaveSalt>,
+        /// Attached funds to pass on to the slave instantiation
+        forward_funds: Option<ForwardFunds>,
+        /// Defaults to the template label prefix followed by a sequence number
+        label: Option<String>,
+        /// Wasm admin of the slave, the master when omitted
+        admin: Option<AdminPolicy>,
+    },
+    /// Deploys every slave in one transaction. The fee is due per slave and
+    /// forwarded funds are taken from the attached coins in order.
+    DeploySlaves {
+        slaves: Vec<SlaveSpec>,
+    },
+    /// Relays `msg` to a slave owned by the sender
+    ExecuteOnSlave {
+        slave: String,
+        msg: Binary,
+        /// Defaults to forwarding nothing, the rest of the attached funds is refunded
+        funds: Option<ForwardFunds>,
+        /// Return the data set by the slave as the data of this call
+        return_data: Option<bool>,
+    },
+    /// Hands a slave over to `new_owner`. With `require_accept` the transfer
+    /// only completes once the new owner sends `AcceptSlave`.
+    TransferSlave {
+        slave: String,
+        new_owner: String,
+        require_accept: Option<bool>,
+    },
+    AcceptSlave {
+        slave: String,
+    },
+    /// Withdraws a transfer that was not accepted yet
+    CancelSlaveTransfer {
+        slave: String,
+    },
+    /// Called by a slave during its instantiation to confirm itself
+    RegisterSlave {},
+    /// Archives a slave, optionally giving up the master's wasm admin rights
+    DecommissionSlave {
+        slave: String,
+        mode: DecommissionMode,
+    },
+    /// Sends `action` to a page of the active slaves
+    BroadcastToSlaves {
+        action: SlaveExecuteMsg,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Migrates a page of the slaves administered by the master
+    MigrateSlaves {
+        new_code_id: u64,
+        /// Wasm checksum `new_code_id` must have
+        checksum: HexBinary,
+        msg: Binary,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    UpdateSlaveCodeId {
+        code_id: u64,
+        checksum: HexBinary,
+    },
+    UpdateMaxBatchSize {
+        max_batch_size: u32,
+    },
+    UpdateAllowedDenoms {
+        denoms: Vec<String>,
+    },
+    UpdateDeployFee {
+        fee: Vec<Coin>,
+    },
+    UpdateQuotas {
+        quotas: Quotas,
+    },
+    UpdateFailurePolicy {
+        policy: FailurePolicy,
+    },
+    UpdateCountBounds {
+        bounds: CountBounds,
+    },
+    WithdrawFees {
+        to: String,
+        amount: Vec<Coin>,
+    },
+    SetTemplate {
+        name: String,
+        code_id: u64,
+        checksum: HexBinary,
+        label_prefix: String,
+        /// Must set `master` to this contract so the slaves can register
+        default_msg: Option<Binary>,
+        enabled: bool,
+    },
+    RemoveTemplate {
+        name: String,
+    },
+}
+
+/// Options of a single slave deployment, see `ExecuteMsg::DeploySlave`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct SlaveSpec {
+    pub template: Option<String>,
+    pub count: Option<i32>,
+    pub salt: Option<SlaveSalt>,
+    pub forward_funds: Option<ForwardFunds>,
+    pub label: Option<String>,
+    pub admin: Option<AdminPolicy>,
+}
+
+impl From<SlaveSpec> for ExecuteMsg {
+    fn from(spec: SlaveSpec) -> Self {
+        ExecuteMsg::DeploySlave {
+            template: spec.template,
+            count: spec.count,
+            salt: spec.salt,
+            forward_funds: spec.forward_funds,
+            label: spec.label,
+            admin: spec.admin,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DecommissionMode {
+    /// Clears the wasm admin, the slave can no longer be migrated
+    ClearAdmin {},
+    /// Makes the slave owner the wasm admin
+    HandToOwner {},
+    /// Only archives the registry entry
+    Archive {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminPolicy {
+    /// The master contract, so it can migrate the slave later on
+    Master {},
+    /// Whoever deployed the slave
+    Sender {},
+    Address {
+        address: String,
+    },
+    /// Nobody, the slave cannot be migrated
+    None {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaveSalt {
+    /// Scope the given salt to the deployer by hashing it after their address
+    Custom { salt: Binary },
+    /// Derive the salt from the deployer address and their deployment nonce
+    Derived {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardFunds {
+    /// Forward everything attached to the deployment
+    All {},
+    /// Forward the given part of the attached coins
+    Coins { amount: Vec<Coin> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -20,6 +232,78 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     // GetCount returns the current count as a json-encoded number
     GetCount {},
+    // GetUserCount returns how much an address contributed to the count
+    GetUserCount {
+        address: String,
+    },
+    // UserCounts pages through the contributions ordered by address
+    UserCounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // NamedCounter returns the count, owner and bounds of a named counter
+    NamedCounter {
+        name: String,
+    },
+    // NamedCounters pages through the named counters ordered by name
+    NamedCounters {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // GetCountAtHeight returns the count at the end of the given block
+    GetCountAtHeight {
+        height: u64,
+    },
+    // CountHistory pages through the changes of the count, oldest first
+    CountHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // Config returns the owner and the deployment settings
+    Config {},
+    // CollectedFees returns the deployment fees collected and withdrawn per denom
+    CollectedFees {},
+    // Templates pages through the slave templates ordered by name
+    Templates {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // PredictSlaveAddress computes the Instantiate2 address of a slave deployed by
+    // creator with the given custom salt, or with their next derived salt if no salt is given
+    PredictSlaveAddress {
+        template: Option<String>,
+        salt: Option<Binary>,
+        creator: String,
+    },
+    // Slave returns the registry entry of a single deployed slave
+    Slave {
+        address: String,
+    },
+    // ListSlaves pages through all deployed slaves ordered by address
+    ListSlaves {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // FailedDeployments pages through recorded failures ordered by id
+    FailedDeployments {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // Quota shows how many more slaves `address` may deploy right now
+    Quota {
+        address: String,
+    },
+    // SlavesByOwner pages through the slaves currently owned by `owner`
+    SlavesByOwner {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // AggregateSlaveCounts queries GetCount on a page of slaves and sums them up
+    AggregateSlaveCounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -28,7 +312,201 @@ pub struct CountResponse {
     pub count: i32,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserCountResponse {
+    pub address: Addr,
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserCountsResponse {
+    pub counts: Vec<UserCountResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NamedCounterResponse {
+    pub name: String,
+    pub owner: Addr,
+    pub count: i32,
+    pub bounds: CountBounds,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NamedCountersResponse {
+    pub counters: Vec<NamedCounterResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CountAtHeightResponse {
+    pub height: u64,
+    /// None before the contract was instantiated
+    pub count: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CountChangeResponse {
+    pub id: u64,
+    pub height: u64,
+    pub time: Timestamp,
+    pub actor: Addr,
+    pub old: i32,
+    pub new: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CountHistoryResponse {
+    pub changes: Vec<CountChangeResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: Addr,
+    pub slave_code_id: u64,
+    pub slave_checksum: HexBinary,
+    pub allowed_denoms: Vec<String>,
+    pub deploy_fee: Vec<Coin>,
+    pub max_batch_size: u32,
+    pub quotas: Quotas,
+    pub failure_policy: FailurePolicy,
+    pub count_bounds: CountBounds,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectedFee {
+    pub denom: String,
+    pub collected: Uint128,
+    pub withdrawn: Uint128,
+    pub available: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectedFeesResponse {
+    pub fees: Vec<CollectedFee>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TemplateResponse {
+    pub name: String,
+    pub code_id: u64,
+    pub checksum: HexBinary,
+    pub label_prefix: String,
+    pub default_msg: Option<Binary>,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TemplatesResponse {
+    pub templates: Vec<TemplateResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PredictSlaveAddressResponse {
+    pub address: Addr,
+    pub code_id: u64,
+    pub salt: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SlaveResponse {
+    pub address: Addr,
+    pub creator: Addr,
+    pub owner: Addr,
+    pub pending_owner: Option<Addr>,
+    pub initial_count: Option<i32>,
+    pub code_id: u64,
+    pub template: Option<String>,
+    pub label: String,
+    pub admin: Option<Addr>,
+    pub height: u64,
+    pub time: Timestamp,
+    pub last_migration: Option<MigrationRecord>,
+    pub status: SlaveStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SlavesResponse {
+    pub slaves: Vec<SlaveResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SlaveCount {
+    pub address: Addr,
+    /// None when the slave could not be queried
+    pub count: Option<i32>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AggregateSlaveCountsResponse {
+    pub counts: Vec<SlaveCount>,
+    /// Sum of the counts that could be queried on this page
+    pub total: i64,
+    /// Pass as `start_after` for the next page, None on the last page
+    pub last: Option<Addr>,
+}
+
+// Remaining allowances are None when the respective quota is not set
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QuotaResponse {
+    pub live_slaves: u32,
+    pub remaining_slaves: Option<u32>,
+    pub remaining_in_window: Option<u32>,
+    /// First block of the next rate limit window
+    pub window_resets_at: Option<u64>,
+    pub remaining_global: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FailedDeploymentResponse {
+    pub id: u64,
+    pub requester: Addr,
+    pub code_id: u64,
+    pub label: String,
+    pub error: String,
+    pub height: u64,
+    pub refunded: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FailedDeploymentsResponse {
+    pub failures: Vec<FailedDeploymentResponse>,
+}
+
+// Data of a `DeploySlaves` response, slaves that failed under
+// `FailurePolicy::Record` are left out
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DeploySlavesResponse {
+    pub slaves: Vec<Addr>,
+}
+
+// Data of a `MigrateSlaves` response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateSlavesResponse {
+    pub migrating: u32,
+    pub skipped: u32,
+    /// Pass as `start_after` for the next page, None on the last page
+    pub last: Option<Addr>,
+}
+
+// Data of a `BroadcastToSlaves` response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BroadcastToSlavesResponse {
+    pub targeted: u32,
+    /// Pass as `start_after` for the next page, None on the last page
+    pub last: Option<Addr>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct SlaveInstantiateMsg {
     pub count: i32,
-}
\ No newline at end of file
+    /// Master to confirm the slave with by calling `RegisterSlave`
+    pub master: String,
+}
+
+/// Counter operations understood by the slaves.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaveExecuteMsg {
+    Increment {},
+    Reset { count: i32 },
+}
@@ -1,8 +1,8 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Binary, Coin, HexBinary, Timestamp, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
@@ -11,3 +11,261 @@ pub struct State {
 }
 
 pub const STATE: Item<State> = Item::new("state");
+
+/// Contribution of each address to `State.count`, the counts sum up to it.
+pub const USER_COUNTS: Map<&Addr, i64> = Map::new("user_counts");
+
+/// A single change of the counter.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CountChange {
+    pub height: u64,
+    pub time: Timestamp,
+    pub actor: Addr,
+    pub old: i32,
+    pub new: i32,
+}
+
+/// Counter changes keyed by a sequence number, oldest first. The first entry
+/// is the instantiation, which changes the count from 0.
+pub const COUNT_HISTORY: Map<u64, CountChange> = Map::new("count_history");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    /// Code id used to instantiate new slaves
+    pub slave_code_id: u64,
+    /// Wasm checksum `slave_code_id` must have
+    pub slave_checksum: HexBinary,
+    /// Denoms that may be forwarded to slaves on deployment
+    pub allowed_denoms: Vec<String>,
+    /// Charged for every deployment and kept in the master treasury
+    pub deploy_fee: Vec<Coin>,
+    /// Maximum number of slaves in a single `DeploySlaves`
+    pub max_batch_size: u32,
+    pub quotas: Quotas,
+    pub failure_policy: FailurePolicy,
+    pub count_bounds: CountBounds,
+}
+
+/// Inclusive range the counter has to stay in, None means unbounded.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct CountBounds {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+impl CountBounds {
+    pub fn contains(&self, count: i32) -> bool {
+        self.min.is_none_or(|min| count >= min) && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+/// A counter kept next to the default one in `State`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NamedCounter {
+    pub owner: Addr,
+    pub count: i32,
+    pub bounds: CountBounds,
+}
+
+pub const NAMED_COUNTERS: Map<&str, NamedCounter> = Map::new("named_counters");
+
+/// What happens when a slave instantiation fails.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Revert the whole transaction
+    Revert,
+    /// Refund fee and funds, record the failure and carry on
+    Record,
+}
+
+/// Deployment limits, None means unlimited.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Quotas {
+    /// Live (not archived) slaves a single creator may have
+    pub max_slaves_per_creator: Option<u32>,
+    /// Deployments a single creator may make within a window of blocks
+    pub rate_limit: Option<RateLimit>,
+    /// Live slaves across all creators
+    pub max_slaves: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateLimit {
+    pub max_deployments: u32,
+    pub window_blocks: u64,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Deployment fees of a single denom that went through the treasury.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct FeeTotal {
+    pub collected: Uint128,
+    pub withdrawn: Uint128,
+}
+
+pub const FEES: Map<&str, FeeTotal> = Map::new("fees");
+
+/// Named flavour of slave contract the owner allows to be deployed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SlaveTemplate {
+    pub code_id: u64,
+    /// Wasm checksum `code_id` must have
+    pub checksum: HexBinary,
+    pub label_prefix: String,
+    /// Instantiate message used when the deployer does not pass a count
+    pub default_msg: Option<Binary>,
+    pub enabled: bool,
+}
+
+pub const TEMPLATES: Map<&str, SlaveTemplate> = Map::new("templates");
+
+/// Registry entry for a slave contract instantiated by this master.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SlaveInfo {
+    pub creator: Addr,
+    /// Account allowed to drive and transfer the slave through the master
+    pub owner: Addr,
+    /// Set while a transfer waits to be accepted by the new owner
+    pub pending_owner: Option<Addr>,
+    /// None when the slave was instantiated from a template default message
+    pub initial_count: Option<i32>,
+    pub code_id: u64,
+    pub template: Option<String>,
+    pub label: String,
+    /// Wasm admin the slave was instantiated with
+    pub admin: Option<Addr>,
+    pub height: u64,
+    pub time: Timestamp,
+    /// Outcome of the latest `MigrateSlaves` run that covered this slave
+    pub last_migration: Option<MigrationRecord>,
+    pub status: SlaveStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaveStatus {
+    /// Instantiated, but the slave did not register itself yet
+    Pending,
+    Active,
+    /// Decommissioned, kept in the registry for reference only
+    Archived,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrationRecord {
+    pub code_id: u64,
+    pub height: u64,
+    /// Set when the migration failed and the slave stayed on its code id
+    pub error: Option<String>,
+}
+
+pub struct SlaveIndexes<'a> {
+    pub owner: MultiIndex<'a, Addr, SlaveInfo, &'a Addr>,
+}
+
+impl<'a> IndexList<SlaveInfo> for SlaveIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<SlaveInfo>> + '_> {
+        let v: Vec<&dyn Index<SlaveInfo>> = vec![&self.owner];
+        Box::new(v.into_iter())
+    }
+}
+
+fn slave_owner(_pk: &[u8], info: &SlaveInfo) -> Addr {
+    info.owner.clone()
+}
+
+/// Slave registry keyed by the slave contract address and indexed by owner.
+pub const SLAVES: IndexedMap<&Addr, SlaveInfo, SlaveIndexes> = IndexedMap::new(
+    "slaves",
+    SlaveIndexes {
+        owner: MultiIndex::new(slave_owner, "slaves", "slaves__owner"),
+    },
+);
+
+/// Number of derived-salt deployments per deployer, part of the next derived salt.
+pub const SALT_NONCES: Map<&Addr, u64> = Map::new("salt_nonces");
+
+/// Deployment counters of a single creator, checked against the quotas.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct CreatorStats {
+    /// Slaves deployed and not archived yet
+    pub live_slaves: u32,
+    /// First block of the current rate limit window
+    pub window_start: u64,
+    pub window_deployments: u32,
+}
+
+pub const CREATOR_STATS: Map<&Addr, CreatorStats> = Map::new("creator_stats");
+
+/// Slaves that registered themselves before the master handled their
+/// instantiate reply, with the height of the registration.
+pub const EARLY_REGISTRATIONS: Map<&Addr, u64> = Map::new("early_registrations");
+
+/// Slaves deployed and not archived yet, across all creators.
+pub const LIVE_SLAVES: Item<u64> = Item::new("live_slaves");
+
+/// Number of slaves deployed so far, used to suffix default labels.
+pub const SLAVE_SEQ: Item<u64> = Item::new("slave_seq");
+
+/// Last id handed out to a submessage, every submessage gets its own reply id.
+pub const REPLY_ID: Item<u64> = Item::new("reply_id");
+
+/// Deployment request waiting for its instantiate reply.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingDeployment {
+    pub creator: Addr,
+    pub count: Option<i32>,
+    pub code_id: u64,
+    pub template: Option<String>,
+    pub label: String,
+    pub admin: Option<Addr>,
+    /// Funds sent along with the instantiation
+    pub funds: Vec<Coin>,
+    /// Deployment fee charged for this slave
+    pub fee: Vec<Coin>,
+    /// Set when the deployment is part of a `DeploySlaves` batch
+    pub batch: Option<BatchPosition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BatchPosition {
+    /// Id shared by all deployments of the batch
+    pub id: u64,
+    pub index: u32,
+    pub size: u32,
+}
+
+/// Deployments keyed by the reply id of their instantiate submessage.
+pub const PENDING_DEPLOYMENTS: Map<u64, PendingDeployment> = Map::new("pending_deployments");
+
+/// Slaves of a batch in flight, returned once its last reply arrives.
+pub const BATCH_SLAVES: Map<u64, Vec<Addr>> = Map::new("batch_slaves");
+
+/// Migration submessage waiting for its reply.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingMigration {
+    pub slave: Addr,
+    pub code_id: u64,
+}
+
+pub const PENDING_MIGRATIONS: Map<u64, PendingMigration> = Map::new("pending_migrations");
+
+/// Slaves of relayed executions whose response data is returned to the caller.
+pub const PENDING_RELAYS: Map<u64, Addr> = Map::new("pending_relays");
+
+/// Slave instantiation that failed under `FailurePolicy::Record`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FailedDeployment {
+    pub requester: Addr,
+    pub code_id: u64,
+    pub label: String,
+    pub error: String,
+    pub height: u64,
+    /// Fee and funds sent back to the requester
+    pub refunded: Vec<Coin>,
+}
+
+/// Failed deployments keyed by the reply id of their instantiate submessage.
+pub const FAILED_DEPLOYMENTS: Map<u64, FailedDeployment> = Map::new("failed_deployments");